@@ -0,0 +1,204 @@
+use crate::cli::CalibrateArgs;
+use crate::device::detect_devices;
+use crate::input::{TouchpadBounds, TouchpadReader};
+use crate::layouts::{
+    calibrated_hitboxes, calibrated_layout_toml, parse_key_code, CalibrationTarget, FileLayout,
+};
+use crate::numpad::{ContactChange, NumpadState, TouchPosition};
+use anyhow::{bail, Context, Result};
+use evdev::InputEvent;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Keys tapped when `--keys` is not given, in G634JY reading order
+pub const DEFAULT_CALIBRATION_KEYS: &[&str] = &[
+    "KEY_KP7",
+    "KEY_KP8",
+    "KEY_KP9",
+    "KEY_KPSLASH",
+    "KEY_KP4",
+    "KEY_KP5",
+    "KEY_KP6",
+    "KEY_KPASTERISK",
+    "KEY_BACKSPACE",
+    "KEY_KP1",
+    "KEY_KP2",
+    "KEY_KP3",
+    "KEY_KPMINUS",
+    "KEY_KPENTER",
+    "KEY_KP0",
+    "KEY_KPDOT",
+    "KEY_KPPLUS",
+];
+
+const DETECT_TRY_TIMES: u32 = 5;
+const DETECT_TRY_SLEEP_MS: u64 = 100;
+
+/// Turns raw touchpad events into finger-down positions
+///
+/// Positions are taken on the `SYN_REPORT` that reports a finger landing,
+/// the same point the driver uses to pick a key.
+pub struct TapRecorder {
+    state: NumpadState,
+    bounds: TouchpadBounds,
+}
+
+impl TapRecorder {
+    pub fn new(bounds: TouchpadBounds) -> Self {
+        Self {
+            state: NumpadState::new(),
+            bounds,
+        }
+    }
+
+    /// Feed one event; returns the tap position when a finger lands
+    pub fn process(&mut self, event: &InputEvent) -> Option<TouchPosition> {
+        self.state
+            .handle_event(event, &self.bounds)
+            .into_iter()
+            .find_map(|change| match change {
+                ContactChange::Down(contact) => Some(contact.position),
+                ContactChange::Up(_) => None,
+            })
+    }
+}
+
+/// Walk the user through tapping every target and write the layout file
+pub fn run_calibration(args: CalibrateArgs) -> Result<()> {
+    if args.taps == 0 {
+        bail!("--taps must be at least 1");
+    }
+    if args.margin.is_nan() || args.margin <= 0.0 {
+        bail!("--margin must be greater than 0, got {}", args.margin);
+    }
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(format!("{}.toml", args.name)));
+    if output.exists() && !args.force {
+        bail!(
+            "{} already exists; pass --force to overwrite it",
+            output.display()
+        );
+    }
+
+    let mut targets = vec![CalibrationTarget::Toggle, CalibrationTarget::Calculator];
+    for name in &args.keys {
+        targets.push(CalibrationTarget::Key(parse_key_code(name)?));
+    }
+
+    let devices = detect_devices(DETECT_TRY_TIMES, Duration::from_millis(DETECT_TRY_SLEEP_MS))
+        .context("Failed to detect devices")?;
+    // The touchpad is never grabbed, so the cursor keeps moving while tapping
+    let mut touchpad =
+        TouchpadReader::open(&devices.touchpad.event_path).context("Failed to open touchpad")?;
+    let mut recorder = TapRecorder::new(touchpad.bounds());
+
+    println!(
+        "Calibrating {} ({}). Tap each target {} times: its centre and each corner.",
+        devices.touchpad.name, devices.touchpad.event_path, args.taps
+    );
+
+    let mut taps = Vec::with_capacity(targets.len());
+    for target in targets {
+        print!("Tap {}:", target);
+        let _ = io::stdout().flush();
+
+        let mut points = Vec::with_capacity(args.taps);
+        while points.len() < args.taps {
+            let events = match touchpad.fetch_events() {
+                Ok(events) => events,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(Duration::from_millis(10));
+                    continue;
+                }
+                Err(e) => return Err(e).context("Failed to read touchpad events"),
+            };
+            for event in events {
+                if let Some(position) = recorder.process(&event) {
+                    if points.len() < args.taps {
+                        print!(" ({:.2}, {:.2})", position.x, position.y);
+                        let _ = io::stdout().flush();
+                        points.push((position.x, position.y));
+                    }
+                }
+            }
+        }
+        println!();
+        taps.push((target, points));
+    }
+
+    let hitboxes = calibrated_hitboxes(&taps, args.margin);
+    let toml = calibrated_layout_toml(&args.name, &hitboxes);
+    FileLayout::from_toml(&toml).context("Calibration produced a layout the driver cannot load")?;
+    fs::write(&output, toml).with_context(|| format!("failed to write {}", output.display()))?;
+
+    println!("Wrote {}", output.display());
+    println!(
+        "Check it with `layout validate {}` and `layout render {}`, then copy it to /etc/asus-rog-touchpad/layouts/ to run it by name.",
+        output.display(),
+        output.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use evdev::{AbsoluteAxisCode, EventType, KeyCode, SynchronizationCode};
+
+    fn event(event_type: EventType, code: u16, value: i32) -> InputEvent {
+        InputEvent::new(event_type.0, code, value)
+    }
+
+    #[test]
+    fn records_position_when_finger_lands() {
+        let mut recorder = TapRecorder::new(TouchpadBounds {
+            min_x: 0,
+            max_x: 1000,
+            min_y: 0,
+            max_y: 500,
+        });
+        let syn = event(
+            EventType::SYNCHRONIZATION,
+            SynchronizationCode::SYN_REPORT.0,
+            0,
+        );
+        let down = [
+            event(
+                EventType::ABSOLUTE,
+                AbsoluteAxisCode::ABS_MT_POSITION_X.0,
+                250,
+            ),
+            event(
+                EventType::ABSOLUTE,
+                AbsoluteAxisCode::ABS_MT_POSITION_Y.0,
+                100,
+            ),
+            event(EventType::KEY, KeyCode::BTN_TOOL_FINGER.0, 1),
+        ];
+        let up = event(EventType::KEY, KeyCode::BTN_TOOL_FINGER.0, 0);
+
+        for e in &down {
+            assert_eq!(recorder.process(e), None);
+        }
+        let tap = recorder.process(&syn).unwrap();
+        assert!((tap.x - 0.25).abs() < 1e-9);
+        assert!((tap.y - 0.20).abs() < 1e-9);
+
+        // Moving and lifting the finger records nothing
+        assert_eq!(
+            recorder.process(&event(
+                EventType::ABSOLUTE,
+                AbsoluteAxisCode::ABS_MT_POSITION_X.0,
+                900
+            )),
+            None
+        );
+        assert_eq!(recorder.process(&syn), None);
+        assert_eq!(recorder.process(&up), None);
+        assert_eq!(recorder.process(&syn), None);
+    }
+}
@@ -1,5 +1,11 @@
+use crate::calibrate::{run_calibration, DEFAULT_CALIBRATION_KEYS};
+use crate::device::AUTO_MODEL;
+use crate::layouts::{
+    get_layout, render_ascii, render_svg, validate_layout, LayoutRegistry, LayoutSource,
+    LayoutTransform, NumpadLayout, ASCII_COLUMNS, ASCII_ROWS, DEFAULT_VALIDATION_STEP,
+};
 use anyhow::{bail, Context, Result};
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::env;
 use std::ffi::OsString;
 use std::fs;
@@ -79,19 +85,110 @@ pub enum CliCommand {
 
     /// Clean Cargo build artifacts.
     Clean,
+
+    /// Build a layout file by tapping each key on the touchpad.
+    Calibrate(CalibrateArgs),
+
+    /// Inspect and check numpad layouts.
+    #[command(subcommand, visible_alias = "layouts")]
+    Layout(LayoutCommand),
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct CalibrateArgs {
+    /// Name of the generated layout.
+    #[arg(long, default_value = "custom")]
+    pub name: String,
+
+    /// File to write the layout to [default: <NAME>.toml].
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+
+    /// Comma-separated keys printed on the touchpad, in the order to tap them.
+    #[arg(long, value_delimiter = ',', default_values = DEFAULT_CALIBRATION_KEYS)]
+    pub keys: Vec<String>,
+
+    /// Taps to record per key.
+    #[arg(long, default_value_t = 5)]
+    pub taps: usize,
+
+    /// Margin added around the tapped area, in normalized touchpad units.
+    #[arg(long, default_value_t = 0.01)]
+    pub margin: f64,
+
+    /// Overwrite the output file if it exists.
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum LayoutCommand {
+    /// List built-in and installed layouts with their aliases.
+    List,
+
+    /// Check a layout's hitboxes for overlaps, gaps, and unregistered keys.
+    Validate(ValidateArgs),
+
+    /// Draw a layout's key regions and control zones to scale.
+    Render(RenderArgs),
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct ValidateArgs {
+    /// Layout model or path to a TOML layout file.
+    pub model: String,
+
+    /// Sampling step in normalized touchpad units.
+    #[arg(long, default_value_t = DEFAULT_VALIDATION_STEP)]
+    pub step: f64,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct RenderArgs {
+    /// Layout model or path to a TOML layout file.
+    pub model: String,
+
+    /// Output format.
+    #[arg(long, value_enum, default_value_t = RenderFormat::Ascii)]
+    pub format: RenderFormat,
+
+    /// Layer to draw instead of the numpad layer.
+    #[arg(long)]
+    pub layer: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum RenderFormat {
+    Ascii,
+    Svg,
 }
 
 #[derive(Args, Clone, Debug)]
 pub struct RunArgs {
-    /// Layout model to use.
-    #[arg(default_value = "g634jy")]
+    /// Layout model to use, or "auto" to pick it from DMI and touchpad IDs.
+    #[arg(default_value = AUTO_MODEL)]
     pub model: String,
+
+    /// Configuration file [default: /etc/asus-rog-touchpad/config.toml if present].
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// Mirror or rotate the layout, overriding the configuration file.
+    #[arg(long, value_enum)]
+    pub transform: Option<LayoutTransform>,
+
+    /// Keep the pointer working while the numpad is on.
+    #[arg(long)]
+    pub passthrough: bool,
 }
 
 impl Default for RunArgs {
     fn default() -> Self {
         Self {
-            model: "g634jy".to_string(),
+            model: AUTO_MODEL.to_string(),
+            config: None,
+            transform: None,
+            passthrough: false,
         }
     }
 }
@@ -139,6 +236,10 @@ pub fn execute_command(command: CliCommand) -> Result<()> {
         CliCommand::RemoveService => remove_service(),
         CliCommand::RemoveBinary => remove_binary(),
         CliCommand::Clean => run_command(Command::new("cargo").arg("clean")),
+        CliCommand::Calibrate(args) => run_calibration(args),
+        CliCommand::Layout(LayoutCommand::List) => list_layouts_command(),
+        CliCommand::Layout(LayoutCommand::Validate(args)) => validate_layout_command(args),
+        CliCommand::Layout(LayoutCommand::Render(args)) => render_layout_command(args),
     }
 }
 
@@ -164,9 +265,96 @@ fn is_known_subcommand(value: &str) -> bool {
             | "remove-service"
             | "remove-binary"
             | "clean"
+            | "calibrate"
+            | "layout"
+            | "layouts"
     )
 }
 
+fn list_layouts_command() -> Result<()> {
+    let registry = LayoutRegistry::installed();
+    let mut entries: Vec<_> = registry.entries().iter().collect();
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut rows = vec![[
+        "NAME".to_string(),
+        "ALIASES".to_string(),
+        "TOUCHPAD IDS".to_string(),
+        "SOURCE".to_string(),
+        "DESCRIPTION".to_string(),
+    ]];
+    for entry in entries {
+        let ids: Vec<String> = entry.touchpad_ids.iter().map(|id| id.to_string()).collect();
+        let source = match &entry.source {
+            LayoutSource::Builtin(_) => "built-in".to_string(),
+            LayoutSource::File { path, .. } => path.display().to_string(),
+        };
+        rows.push([
+            entry.name.clone(),
+            entry.aliases.join(", "),
+            ids.join(", "),
+            source,
+            entry.description.clone(),
+        ]);
+    }
+
+    let widths: Vec<usize> = (0..4)
+        .map(|col| rows.iter().map(|row| row[col].len()).max().unwrap_or(0))
+        .collect();
+    for row in rows {
+        let mut line = String::new();
+        for (col, width) in widths.iter().enumerate() {
+            line.push_str(&format!("{:<width$}  ", row[col], width = width));
+        }
+        line.push_str(&row[4]);
+        println!("{}", line.trim_end());
+    }
+    Ok(())
+}
+
+fn validate_layout_command(args: ValidateArgs) -> Result<()> {
+    let layout = get_layout(&args.model)?;
+    let report = validate_layout(layout.as_ref(), args.step);
+    print!("{}", report);
+
+    if report.has_errors() {
+        bail!("layout {} failed validation", layout.name());
+    }
+    Ok(())
+}
+
+fn render_layout_command(args: RenderArgs) -> Result<()> {
+    let layout = get_layout(&args.model)?;
+    let layer = match args.layer {
+        Some(name) => find_layer(layout.as_ref(), &name)?,
+        None => 0,
+    };
+
+    match args.format {
+        RenderFormat::Ascii => print!(
+            "{}",
+            render_ascii(layout.as_ref(), layer, ASCII_COLUMNS, ASCII_ROWS)
+        ),
+        RenderFormat::Svg => print!("{}", render_svg(layout.as_ref(), layer)),
+    }
+    Ok(())
+}
+
+fn find_layer(layout: &dyn NumpadLayout, name: &str) -> Result<usize> {
+    let names: Vec<&str> = (0..layout.layer_count())
+        .map(|layer| layout.layer_name(layer))
+        .collect();
+    match names.iter().position(|layer| *layer == name) {
+        Some(layer) => Ok(layer),
+        None => bail!(
+            "layout {} has no layer {} (layers: {})",
+            layout.name(),
+            name,
+            names.join(", ")
+        ),
+    }
+}
+
 fn build_release() -> Result<()> {
     info("Building release binary...");
     run_command(Command::new("cargo").args(["build", "--release"]))?;
@@ -194,6 +382,17 @@ fn run_debug(args: RunArgs) -> Result<()> {
         env::var("RUST_LOG").unwrap_or_else(|_| "debug".to_string()),
     );
     command.arg(args.model);
+    if let Some(config) = args.config {
+        command.arg("--config").arg(config);
+    }
+    if let Some(transform) = args.transform {
+        if let Some(value) = transform.to_possible_value() {
+            command.arg("--transform").arg(value.get_name());
+        }
+    }
+    if args.passthrough {
+        command.arg("--passthrough");
+    }
     run_command(&mut command)
 }
 
@@ -485,6 +684,53 @@ mod tests {
         assert!(matches!(cli.command, Some(CliCommand::Run(_))));
     }
 
+    #[test]
+    fn run_defaults_to_auto_model() {
+        let cli = Cli::parse_from([BINARY_NAME, "run"]);
+
+        match cli.command {
+            Some(CliCommand::Run(args)) => assert_eq!(args.model, AUTO_MODEL),
+            other => panic!("expected run command, got {other:?}"),
+        }
+        assert_eq!(RunArgs::default().model, AUTO_MODEL);
+    }
+
+    #[test]
+    fn run_accepts_config_path() {
+        let cli = Cli::parse_from([BINARY_NAME, "run", "g634jy", "--config", "numpad.toml"]);
+
+        match cli.command {
+            Some(CliCommand::Run(args)) => {
+                assert_eq!(args.config, Some(PathBuf::from("numpad.toml")));
+            }
+            other => panic!("expected run command, got {other:?}"),
+        }
+        assert_eq!(RunArgs::default().config, None);
+    }
+
+    #[test]
+    fn run_accepts_transform() {
+        let cli = Cli::parse_from([BINARY_NAME, "run", "--transform", "rotate-180"]);
+
+        match cli.command {
+            Some(CliCommand::Run(args)) => {
+                assert_eq!(args.transform, Some(LayoutTransform::Rotate180));
+            }
+            other => panic!("expected run command, got {other:?}"),
+        }
+        assert!(Cli::try_parse_from([BINARY_NAME, "run", "--transform", "sideways"]).is_err());
+    }
+
+    #[test]
+    fn run_accepts_passthrough() {
+        let cli = Cli::parse_from([BINARY_NAME, "run", "g634jy", "--passthrough"]);
+
+        match cli.command {
+            Some(CliCommand::Run(args)) => assert!(args.passthrough),
+            other => panic!("expected run command, got {other:?}"),
+        }
+    }
+
     #[test]
     fn legacy_driver_arguments_are_rewritten_to_run() {
         let args = args_with_legacy_run_subcommand(vec![
@@ -501,6 +747,78 @@ mod tests {
         }
     }
 
+    #[test]
+    fn layout_validate_accepts_model_and_step() {
+        let cli = Cli::parse_from([
+            BINARY_NAME,
+            "layouts",
+            "validate",
+            "my.toml",
+            "--step",
+            "0.01",
+        ]);
+
+        match cli.command {
+            Some(CliCommand::Layout(LayoutCommand::Validate(args))) => {
+                assert_eq!(args.model, "my.toml");
+                assert_eq!(args.step, 0.01);
+            }
+            other => panic!("expected layout validate command, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn layouts_list_parses() {
+        let cli = Cli::parse_from([BINARY_NAME, "layouts", "list"]);
+
+        assert!(matches!(
+            cli.command,
+            Some(CliCommand::Layout(LayoutCommand::List))
+        ));
+    }
+
+    #[test]
+    fn layout_render_defaults_to_ascii() {
+        let cli = Cli::parse_from([BINARY_NAME, "layout", "render", "g634jy"]);
+
+        match cli.command {
+            Some(CliCommand::Layout(LayoutCommand::Render(args))) => {
+                assert_eq!(args.format, RenderFormat::Ascii);
+                assert_eq!(args.layer, None);
+            }
+            other => panic!("expected layout render command, got {other:?}"),
+        }
+
+        let cli = Cli::parse_from([BINARY_NAME, "layout", "render", "g634jy", "--format", "svg"]);
+        assert!(matches!(
+            cli.command,
+            Some(CliCommand::Layout(LayoutCommand::Render(RenderArgs {
+                format: RenderFormat::Svg,
+                ..
+            })))
+        ));
+    }
+
+    #[test]
+    fn calibrate_parses_key_list() {
+        let cli = Cli::parse_from([BINARY_NAME, "calibrate", "--keys", "KEY_KP1,KEY_KP2"]);
+
+        match cli.command {
+            Some(CliCommand::Calibrate(args)) => {
+                assert_eq!(args.keys, ["KEY_KP1", "KEY_KP2"]);
+                assert_eq!(args.name, "custom");
+                assert_eq!(args.output, None);
+            }
+            other => panic!("expected calibrate command, got {other:?}"),
+        }
+
+        let cli = Cli::parse_from([BINARY_NAME, "calibrate"]);
+        match cli.command {
+            Some(CliCommand::Calibrate(args)) => assert_eq!(args.keys, DEFAULT_CALIBRATION_KEYS),
+            other => panic!("expected calibrate command, got {other:?}"),
+        }
+    }
+
     #[test]
     fn recognizes_all_replacement_subcommands() {
         for command in [
@@ -523,6 +841,9 @@ mod tests {
             "remove-service",
             "remove-binary",
             "clean",
+            "calibrate",
+            "layout",
+            "layouts",
         ] {
             assert!(is_known_subcommand(command), "{command}");
         }
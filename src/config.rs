@@ -0,0 +1,497 @@
+use crate::error::{DriverError, Result};
+use crate::i2c::FlashSettings;
+use crate::layouts::{
+    region_keys, ActionDefinition, HitboxLayout, HitboxTolerance, KeyRemap, LayoutTransform,
+    NumpadLayout, RemapSource, RemappedLayout, TransformedLayout,
+};
+use crate::locale::{Locale, LocaleDefinition};
+use crate::numpad::{ActivationGesture, PressMode, PressureGate, RepeatSettings, TapSettings};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Configuration file read when `--config` is not given
+pub const CONFIG_PATH: &str = "/etc/asus-rog-touchpad/config.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigDefinition {
+    /// Region name or key code -> evdev key name
+    #[serde(default)]
+    remap: BTreeMap<String, String>,
+    /// Region name or key code -> action
+    #[serde(default)]
+    actions: BTreeMap<String, ActionDefinition>,
+    #[serde(default)]
+    locale: LocaleDefinition,
+    hitbox: Option<HitboxTolerance>,
+    #[serde(default)]
+    transform: LayoutTransform,
+    tap: Option<TapSettings>,
+    #[serde(default)]
+    passthrough: bool,
+    repeat: Option<RepeatSettings>,
+    #[serde(default)]
+    activation: ActivationGesture,
+    #[serde(default)]
+    press: PressMode,
+    flash: Option<FlashSettings>,
+    pressure_threshold: Option<f64>,
+}
+
+/// Driver configuration applied on top of the selected layout
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Config {
+    /// Key overrides from `[remap]` followed by those from `[actions]`
+    pub remap: Vec<KeyRemap>,
+    /// Keypad output for the installation's locale; `remap` takes precedence
+    pub locale: Locale,
+    /// Replaces the layout's own hitbox tolerance when set
+    pub hitbox: Option<HitboxTolerance>,
+    /// Mirroring or rotation for left-handed use or unusual mounts
+    pub transform: LayoutTransform,
+    /// Emit keys only for taps, so cursor drags over the numpad type nothing
+    pub tap: Option<TapSettings>,
+    /// Keep the pointer working while the numpad is on
+    pub passthrough: bool,
+    /// Repeat held keys from the driver instead of leaving it to the session
+    pub repeat: Option<RepeatSettings>,
+    /// Gesture in the toggle zone that turns the numpad on or off
+    pub activation: ActivationGesture,
+    /// Whether keys follow the finger as it lands, lifts or slides
+    pub press: PressMode,
+    /// Pulse the backlight to confirm keys and dead-zone touches
+    pub flash: Option<FlashSettings>,
+    /// Replaces the layout's own pressure threshold when set; 0.0 turns it off
+    pub pressure_threshold: Option<f64>,
+}
+
+impl Config {
+    pub fn from_toml(source: &str) -> Result<Self> {
+        let definition: ConfigDefinition =
+            toml::from_str(source).map_err(|e| DriverError::InvalidConfig(e.to_string()))?;
+
+        if let Some(from) = definition
+            .actions
+            .keys()
+            .find(|from| definition.remap.contains_key(*from))
+        {
+            return Err(DriverError::InvalidConfig(format!(
+                "{} is in both [remap] and [actions]",
+                from
+            )));
+        }
+
+        if let Some(hitbox) = &definition.hitbox {
+            hitbox.check().map_err(DriverError::InvalidConfig)?;
+        }
+        if let Some(tap) = &definition.tap {
+            tap.check().map_err(DriverError::InvalidConfig)?;
+        }
+        if let Some(repeat) = &definition.repeat {
+            repeat.check().map_err(DriverError::InvalidConfig)?;
+        }
+        if let Some(flash) = &definition.flash {
+            flash.check().map_err(DriverError::InvalidConfig)?;
+        }
+        if let Some(threshold) = definition.pressure_threshold {
+            PressureGate::check_threshold(threshold).map_err(DriverError::InvalidConfig)?;
+        }
+        definition
+            .activation
+            .check()
+            .map_err(DriverError::InvalidConfig)?;
+
+        let mut remap = KeyRemap::parse_map(&definition.remap)?;
+        remap.extend(KeyRemap::parse_actions(&definition.actions)?);
+        Ok(Self {
+            remap,
+            locale: Locale::from_definition(&definition.locale)?,
+            hitbox: definition.hitbox,
+            transform: definition.transform,
+            tap: definition.tap,
+            passthrough: definition.passthrough,
+            repeat: definition.repeat,
+            activation: definition.activation,
+            press: definition.press,
+            flash: definition.flash,
+            pressure_threshold: definition.pressure_threshold,
+        })
+    }
+
+    /// Load `path`, or [`CONFIG_PATH`] if it exists when no path is given
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path,
+            None if Path::new(CONFIG_PATH).is_file() => Path::new(CONFIG_PATH),
+            None => return Ok(Self::default()),
+        };
+
+        let source = fs::read_to_string(path).map_err(|e| {
+            DriverError::InvalidConfig(format!("Cannot read {}: {}", path.display(), e))
+        })?;
+        Self::from_toml(&source).map_err(|e| match e {
+            DriverError::InvalidConfig(reason) => {
+                DriverError::InvalidConfig(format!("{}: {}", path.display(), reason))
+            }
+            other => other,
+        })
+    }
+
+    /// Wrap the layout with this configuration's key overrides, hitbox tolerance
+    /// and transform
+    ///
+    /// Locale overrides only touch keys the layout has and lose to `[remap]`
+    /// and `[actions]` entries for the same key.
+    pub fn apply(&self, layout: Arc<dyn NumpadLayout>) -> Result<Arc<dyn NumpadLayout>> {
+        let mut layout = self.apply_remap(layout)?;
+        if let Some(tolerance) = self.hitbox {
+            layout = Arc::new(HitboxLayout::new(layout, tolerance));
+        }
+        if self.transform != LayoutTransform::None {
+            layout = Arc::new(TransformedLayout::new(layout, self.transform));
+        }
+        Ok(layout)
+    }
+
+    fn apply_remap(&self, layout: Arc<dyn NumpadLayout>) -> Result<Arc<dyn NumpadLayout>> {
+        let region_keys = region_keys(layout.as_ref());
+        let mut remap = self.remap.clone();
+        remap.extend(
+            self.locale
+                .remap
+                .iter()
+                .filter(
+                    |entry| matches!(entry.from, RemapSource::Key(key) if region_keys.contains(&key)),
+                )
+                .cloned(),
+        );
+
+        if remap.is_empty() {
+            return Ok(layout);
+        }
+        Ok(Arc::new(RemappedLayout::new(layout, &remap)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::{get_layout, Action, FileLayout, KeyStroke};
+    use evdev::KeyCode;
+
+    #[test]
+    fn applies_remap_before_keys_are_registered() {
+        let config = Config::from_toml(
+            r#"
+[remap]
+KEY_BACKSPACE = "KEY_KPEQUAL"
+"#,
+        )
+        .unwrap();
+        let layout = config.apply(get_layout("g634jy").unwrap()).unwrap();
+
+        assert!(layout.all_keys().contains(&KeyCode::KEY_KPEQUAL));
+        assert_eq!(
+            layout.key_at_position(0.87, 0.40),
+            Some(KeyCode::KEY_KPEQUAL)
+        );
+    }
+
+    #[test]
+    fn applies_actions_and_registers_their_keys() {
+        let config = Config::from_toml(
+            r#"
+[actions]
+kp0 = { text = "00" }
+KEY_KPDOT = { combo = "Ctrl+C" }
+"#,
+        )
+        .unwrap();
+        let layout = config.apply(get_layout("g634jy").unwrap()).unwrap();
+
+        assert_eq!(
+            layout.action_at_layer_position(0, 0.14, 0.87),
+            Some(Action::Text("00".to_string()))
+        );
+        assert_eq!(
+            layout.action_at_layer_position(0, 0.50, 0.87),
+            Some(Action::Combo(KeyStroke::new(
+                &[KeyCode::KEY_LEFTCTRL],
+                KeyCode::KEY_C
+            )))
+        );
+        // Hold/release keys are untouched
+        assert_eq!(
+            layout.action_at_layer_position(0, 0.14, 0.15),
+            Some(Action::Key(KeyCode::KEY_KP7))
+        );
+        for key in [KeyCode::KEY_0, KeyCode::KEY_LEFTCTRL, KeyCode::KEY_C] {
+            assert!(layout.all_keys().contains(&key), "{key:?}");
+        }
+    }
+
+    #[test]
+    fn locale_applies_to_present_keys_after_user_overrides() {
+        let config = Config::from_toml(
+            r#"
+[locale]
+name = "de_DE.UTF-8"
+keys = { KEY_KPASTERISK = { text = "×" }, KEY_KPEQUAL = { text = "=" } }
+"#,
+        )
+        .unwrap();
+        let layout = config.apply(get_layout("g634jy").unwrap()).unwrap();
+
+        assert_eq!(
+            layout.action_at_layer_position(0, 0.50, 0.87),
+            Some(Action::Key(KeyCode::KEY_KPCOMMA))
+        );
+        assert_eq!(
+            layout.action_at_layer_position(0, 0.87, 0.40),
+            Some(Action::Key(KeyCode::KEY_BACKSPACE))
+        );
+        assert!(layout.all_keys().contains(&KeyCode::KEY_KPCOMMA));
+        assert!(!layout.all_keys().contains(&KeyCode::KEY_KPDOT));
+
+        let config = Config::from_toml(
+            r#"
+[remap]
+KEY_KPDOT = "KEY_DOT"
+
+[locale]
+decimal_separator = "kpcomma"
+"#,
+        )
+        .unwrap();
+        let layout = config.apply(get_layout("g634jy").unwrap()).unwrap();
+        assert_eq!(layout.key_at_position(0.50, 0.87), Some(KeyCode::KEY_DOT));
+    }
+
+    #[test]
+    fn hitbox_overrides_layout_tolerance() {
+        let config = Config::from_toml(
+            r#"
+[remap]
+KEY_KP7 = "KEY_7"
+
+[hitbox]
+mode = "expand"
+"#,
+        )
+        .unwrap();
+        let layout = config.apply(get_layout("g634jy").unwrap()).unwrap();
+
+        assert_eq!(layout.hitbox_tolerance(), HitboxTolerance::Expand);
+        assert_eq!(layout.key_at_position(0.23, 0.15), Some(KeyCode::KEY_7));
+
+        let err = Config::from_toml("[hitbox]\nmode = \"snap\"\nmax_distance = 2.0").unwrap_err();
+        assert!(err.to_string().contains("hitbox max_distance"), "{err}");
+    }
+
+    #[test]
+    fn tap_table_enables_tap_mode() {
+        assert_eq!(Config::from_toml("").unwrap().tap, None);
+        assert_eq!(
+            Config::from_toml("[tap]\nmax_duration_ms = 180")
+                .unwrap()
+                .tap,
+            Some(TapSettings {
+                max_duration_ms: 180,
+                ..TapSettings::default()
+            })
+        );
+
+        let err = Config::from_toml("[tap]\nmax_distance = 1.5").unwrap_err();
+        assert!(err.to_string().contains("tap max_distance"), "{err}");
+        assert!(Config::from_toml("[tap]\nmax_travel = 0.1").is_err());
+    }
+
+    #[test]
+    fn repeat_table_sets_delay_and_rate() {
+        assert_eq!(Config::from_toml("").unwrap().repeat, None);
+        assert_eq!(
+            Config::from_toml("[repeat]\nrate = 30").unwrap().repeat,
+            Some(RepeatSettings {
+                delay_ms: 500,
+                rate: 30
+            })
+        );
+
+        let err = Config::from_toml("[repeat]\nrate = 0").unwrap_err();
+        assert!(err.to_string().contains("repeat rate"), "{err}");
+    }
+
+    #[test]
+    fn activation_table_selects_gesture() {
+        assert_eq!(
+            Config::from_toml("").unwrap().activation,
+            ActivationGesture::Touch
+        );
+        assert_eq!(
+            Config::from_toml("[activation]\ngesture = \"long-press\"\nhold_ms = 800")
+                .unwrap()
+                .activation,
+            ActivationGesture::LongPress { hold_ms: 800 }
+        );
+        assert!(Config::from_toml("[activation]\ngesture = \"triple-tap\"").is_err());
+        let err =
+            Config::from_toml("[activation]\ngesture = \"swipe-in\"\ndistance = -0.1").unwrap_err();
+        assert!(err.to_string().contains("swipe-in distance"), "{err}");
+    }
+
+    #[test]
+    fn press_selects_when_keys_are_sent() {
+        assert_eq!(Config::from_toml("").unwrap().press, PressMode::Touch);
+        assert_eq!(
+            Config::from_toml("press = \"release\"").unwrap().press,
+            PressMode::Release
+        );
+        assert!(Config::from_toml("press = \"hover\"").is_err());
+    }
+
+    #[test]
+    fn flash_table_enables_led_feedback() {
+        assert_eq!(Config::from_toml("").unwrap().flash, None);
+        assert_eq!(
+            Config::from_toml("[flash]\ndead_zones = false")
+                .unwrap()
+                .flash,
+            Some(FlashSettings {
+                dead_zones: false,
+                ..FlashSettings::default()
+            })
+        );
+
+        let err = Config::from_toml("[flash]\nduration_ms = 0").unwrap_err();
+        assert!(err.to_string().contains("flash duration_ms"), "{err}");
+    }
+
+    #[test]
+    fn pressure_threshold_overrides_layout_default() {
+        assert_eq!(Config::from_toml("").unwrap().pressure_threshold, None);
+        assert_eq!(
+            Config::from_toml("pressure_threshold = 0.25")
+                .unwrap()
+                .pressure_threshold,
+            Some(0.25)
+        );
+        let err = Config::from_toml("pressure_threshold = -0.1").unwrap_err();
+        assert!(err.to_string().contains("pressure_threshold"), "{err}");
+    }
+
+    #[test]
+    fn passthrough_is_off_by_default() {
+        assert!(!Config::from_toml("").unwrap().passthrough);
+        assert!(Config::from_toml("passthrough = true").unwrap().passthrough);
+    }
+
+    #[test]
+    fn transform_mirrors_remapped_layout() {
+        let config = Config::from_toml(
+            r#"
+transform = "mirror-horizontal"
+
+[remap]
+KEY_KP7 = "KEY_7"
+"#,
+        )
+        .unwrap();
+        let layout = config.apply(get_layout("g634jy").unwrap()).unwrap();
+
+        assert_eq!(layout.key_at_position(0.86, 0.15), Some(KeyCode::KEY_7));
+        assert_eq!(
+            Config::from_toml("transform = \"rotate-180\"")
+                .unwrap()
+                .transform,
+            LayoutTransform::Rotate180
+        );
+        assert!(Config::from_toml("transform = \"rotate-90\"").is_err());
+    }
+
+    #[test]
+    fn stacked_wrappers_keep_layout_settings() {
+        let config = Config::from_toml(
+            r#"
+transform = "mirror-horizontal"
+
+[remap]
+KEY_KP1 = "KEY_1"
+
+[hitbox]
+mode = "expand"
+"#,
+        )
+        .unwrap();
+        let layout = FileLayout::from_toml(
+            r#"
+name = "settings"
+pressure_threshold = 0.4
+i2c_address = 0x15
+
+[palm]
+touch_major = 1200
+
+[[keys]]
+key = "KEY_KP1"
+x = [0.2, 0.5]
+y = [0.3, 0.6]
+
+[[sliders]]
+name = "volume"
+action = { decrease = "KEY_VOLUMEDOWN", increase = "KEY_VOLUMEUP", step = 0.1 }
+axis = "vertical"
+x = [0.95, 1.0]
+y = [0.3, 0.9]
+"#,
+        )
+        .unwrap();
+        let layout = config.apply(Arc::new(layout)).unwrap();
+
+        assert_eq!(layout.name(), "settings");
+        assert_eq!(layout.palm_rejection().touch_major, Some(1200));
+        assert_eq!(layout.pressure_threshold(), Some(0.4));
+        assert_eq!(layout.i2c_address(), Some(0x15));
+        assert_eq!(layout.hitbox_tolerance(), HitboxTolerance::Expand);
+        assert_eq!(layout.sliders().len(), 1);
+        assert_eq!(layout.slider_at_position(0.02, 0.5).unwrap().name, "volume");
+        assert_eq!(layout.key_at_position(0.65, 0.45), Some(KeyCode::KEY_1));
+    }
+
+    #[test]
+    fn rejects_key_in_both_remap_and_actions() {
+        let err = Config::from_toml(
+            r#"
+[remap]
+kp0 = "KEY_0"
+
+[actions]
+kp0 = { text = "00" }
+"#,
+        )
+        .unwrap_err();
+
+        assert!(err.to_string().contains("kp0 is in both"), "{err}");
+    }
+
+    #[test]
+    fn empty_config_keeps_layout() {
+        let config = Config::from_toml("").unwrap();
+        let layout = get_layout("g634jy").unwrap();
+
+        assert!(Arc::ptr_eq(&config.apply(layout.clone()).unwrap(), &layout));
+    }
+
+    #[test]
+    fn rejects_unknown_fields_and_key_names() {
+        assert!(Config::from_toml("remaps = {}").is_err());
+
+        let err = Config::from_toml("[remap]\nbackspace = \"KEY_EQUALS\"").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid configuration: remap backspace: unknown key name \"KEY_EQUALS\" (expected an evdev name such as KEY_KPEQUAL)"
+        );
+    }
+}
@@ -1,18 +1,56 @@
 use crate::error::{DriverError, Result};
 use log::{debug, info};
 use regex::Regex;
+use std::fmt;
 use std::fs;
+use std::str::FromStr;
 use std::thread;
 use std::time::Duration;
 
 const PROC_DEVICES_PATH: &str = "/proc/bus/input/devices";
 
+/// USB/HID vendor and product ID pair, as printed in the `I:` line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceId {
+    pub vendor: u16,
+    pub product: u16,
+}
+
+impl FromStr for DeviceId {
+    type Err = DriverError;
+
+    /// Parse `vendor:product` in hex, e.g. `2808:0108`
+    fn from_str(s: &str) -> Result<Self> {
+        let parse = |part: &str| u16::from_str_radix(part.trim(), 16).ok();
+        s.split_once(':')
+            .and_then(|(vendor, product)| {
+                Some(Self {
+                    vendor: parse(vendor)?,
+                    product: parse(product)?,
+                })
+            })
+            .ok_or_else(|| {
+                DriverError::ParseError(format!(
+                    "touchpad ID {:?} is not vendor:product in hex, e.g. 2808:0108",
+                    s
+                ))
+            })
+    }
+}
+
+impl fmt::Display for DeviceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04x}:{:04x}", self.vendor, self.product)
+    }
+}
+
 /// Information about a detected input device
 #[derive(Debug, Clone)]
 pub struct InputDeviceInfo {
     pub name: String,
     pub event_path: String,
     pub i2c_bus: Option<u8>,
+    pub id: Option<DeviceId>,
 }
 
 /// Information about detected devices needed by the driver
@@ -97,6 +135,10 @@ fn parse_devices(
                         name: extract_name(name_line),
                         event_path: String::new(),
                         i2c_bus: None,
+                        id: lines
+                            .iter()
+                            .find(|l| l.starts_with("I: "))
+                            .and_then(|l| parse_device_id(l)),
                     };
 
                     // Extract I2C bus from sysfs line
@@ -131,6 +173,7 @@ fn parse_devices(
                         name: extract_name(name_line),
                         event_path: String::new(),
                         i2c_bus: None,
+                        id: None,
                     };
 
                     if let Some(handlers_line) = lines.iter().find(|l| l.starts_with("H: ")) {
@@ -157,6 +200,21 @@ fn parse_devices(
     (touchpad, keyboard)
 }
 
+/// Parse `I: Bus=0018 Vendor=2808 Product=0108 Version=0100`
+fn parse_device_id(id_line: &str) -> Option<DeviceId> {
+    let field = |key: &str| {
+        id_line
+            .split_whitespace()
+            .find_map(|part| part.strip_prefix(key))
+            .and_then(|value| u16::from_str_radix(value, 16).ok())
+    };
+
+    Some(DeviceId {
+        vendor: field("Vendor=")?,
+        product: field("Product=")?,
+    })
+}
+
 fn extract_name(name_line: &str) -> String {
     name_line
         .trim_start_matches("N: Name=\"")
@@ -167,6 +225,7 @@ fn extract_name(name_line: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::device::select_hardware;
 
     fn regexes() -> (Regex, Regex, Regex, Regex) {
         (
@@ -196,9 +255,42 @@ H: Handlers=mouse1 event17
         assert_eq!(touchpad.name, "ASUF1416:00 2808:0108 Touchpad");
         assert_eq!(touchpad.event_path, "/dev/input/event17");
         assert_eq!(touchpad.i2c_bus, Some(0));
+        assert_eq!(
+            touchpad.id,
+            Some(DeviceId {
+                vendor: 0x2808,
+                product: 0x0108
+            })
+        );
         assert!(keyboard.is_none());
     }
 
+    #[test]
+    fn selects_hardware_from_proc_devices_fixture() {
+        let content = r#"I: Bus=0011 Vendor=0001 Product=0001 Version=ab41
+N: Name="AT Translated Set 2 keyboard"
+P: Phys=isa0060/serio0/input0
+S: Sysfs=/devices/platform/i8042/serio0/input/input0
+H: Handlers=sysrq kbd event0 leds
+
+I: Bus=0018 Vendor=2808 Product=0108 Version=0100
+N: Name="ASUF1416:00 2808:0108 Touchpad"
+P: Phys=i2c-ASUF1416:00
+S: Sysfs=/devices/platform/AMDI0010:03/i2c-0/i2c-ASUF1416:00/0018:2808:0108.0001/input/input19
+H: Handlers=mouse1 event17
+"#;
+        let (touchpad_re, keyboard_re, i2c_re, event_re) = regexes();
+        let (touchpad, _) = parse_devices(content, &touchpad_re, &keyboard_re, &i2c_re, &event_re);
+        let touchpad = touchpad.expect("touchpad should be parsed");
+
+        let selected = select_hardware(None, touchpad.id).expect("touchpad ID should match");
+        assert_eq!(selected.entry.layout, "g634jy");
+        assert_eq!(selected.entry.i2c_address, 0x38);
+
+        let selected = select_hardware(Some("ROG Strix G634JYR_G634JYR"), touchpad.id).unwrap();
+        assert!(selected.matched_dmi && selected.matched_touchpad);
+    }
+
     #[test]
     fn parses_keyboard_when_present() {
         let content = r#"I: Bus=0011 Vendor=0001 Product=0001 Version=ab41
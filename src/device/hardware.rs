@@ -0,0 +1,183 @@
+use super::DeviceId;
+use log::debug;
+use std::fs;
+
+/// Model argument that selects the layout from the hardware table
+pub const AUTO_MODEL: &str = "auto";
+
+const DMI_PRODUCT_NAME_PATH: &str = "/sys/class/dmi/id/product_name";
+
+/// Known laptop with a numpad touchpad
+#[derive(Debug, PartialEq, Eq)]
+pub struct HardwareEntry {
+    /// Model code searched for in the DMI product name (e.g. "G634JY")
+    pub dmi_model: &'static str,
+    /// Touchpad vendor:product ID, when it identifies the model
+    pub touchpad_id: Option<DeviceId>,
+    pub layout: &'static str,
+    pub i2c_address: u8,
+}
+
+/// Hardware table used by the `auto` model
+pub const HARDWARE_TABLE: &[HardwareEntry] = &[
+    HardwareEntry {
+        dmi_model: "G634JY",
+        touchpad_id: Some(DeviceId {
+            vendor: 0x2808,
+            product: 0x0108,
+        }),
+        layout: "g634jy",
+        i2c_address: 0x38,
+    },
+    HardwareEntry {
+        dmi_model: "UX433FA",
+        touchpad_id: None,
+        layout: "ux433fa",
+        i2c_address: 0x15,
+    },
+    HardwareEntry {
+        dmi_model: "UX425EA",
+        touchpad_id: None,
+        layout: "ux433fa",
+        i2c_address: 0x15,
+    },
+    HardwareEntry {
+        dmi_model: "UX581",
+        touchpad_id: None,
+        layout: "ux581",
+        i2c_address: 0x15,
+    },
+    HardwareEntry {
+        dmi_model: "M433IA",
+        touchpad_id: None,
+        layout: "m433ia",
+        i2c_address: 0x15,
+    },
+    HardwareEntry {
+        dmi_model: "UP5401EA",
+        touchpad_id: None,
+        layout: "m433ia",
+        i2c_address: 0x15,
+    },
+    HardwareEntry {
+        dmi_model: "GA401",
+        touchpad_id: None,
+        layout: "ga401",
+        i2c_address: 0x15,
+    },
+    HardwareEntry {
+        dmi_model: "GA502",
+        touchpad_id: None,
+        layout: "ga401",
+        i2c_address: 0x15,
+    },
+    HardwareEntry {
+        dmi_model: "GX701",
+        touchpad_id: None,
+        layout: "gx701",
+        i2c_address: 0x15,
+    },
+];
+
+/// Hardware table entry chosen for this machine
+#[derive(Debug, PartialEq, Eq)]
+pub struct HardwareMatch {
+    pub entry: &'static HardwareEntry,
+    pub matched_dmi: bool,
+    pub matched_touchpad: bool,
+}
+
+/// Read the DMI product name, e.g. "ROG Strix G634JY_G634JY"
+pub fn read_product_name() -> Option<String> {
+    match fs::read_to_string(DMI_PRODUCT_NAME_PATH) {
+        Ok(name) => Some(name.trim().to_string()),
+        Err(e) => {
+            debug!("Cannot read {}: {}", DMI_PRODUCT_NAME_PATH, e);
+            None
+        }
+    }
+}
+
+/// Pick the best hardware table entry for a DMI product name and touchpad ID
+///
+/// An entry matching both wins over a DMI-only match, which wins over a
+/// touchpad-ID-only match.
+pub fn select_hardware(
+    product_name: Option<&str>,
+    touchpad_id: Option<DeviceId>,
+) -> Option<HardwareMatch> {
+    let product_name = product_name.map(str::to_uppercase);
+
+    HARDWARE_TABLE
+        .iter()
+        .map(|entry| HardwareMatch {
+            entry,
+            matched_dmi: product_name
+                .as_deref()
+                .is_some_and(|name| name.contains(entry.dmi_model)),
+            matched_touchpad: touchpad_id.is_some() && entry.touchpad_id == touchpad_id,
+        })
+        .filter(|m| m.matched_dmi || m.matched_touchpad)
+        .max_by_key(|m| (m.matched_dmi && m.matched_touchpad, m.matched_dmi))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const G634JY_ID: DeviceId = DeviceId {
+        vendor: 0x2808,
+        product: 0x0108,
+    };
+
+    #[test]
+    fn selects_layout_from_dmi_product_name() {
+        let selected = select_hardware(Some("ZenBook UX433FA_UX433FA\n"), None).unwrap();
+
+        assert_eq!(selected.entry.layout, "ux433fa");
+        assert_eq!(selected.entry.i2c_address, 0x15);
+        assert!(selected.matched_dmi);
+        assert!(!selected.matched_touchpad);
+
+        let selected = select_hardware(Some("ROG Zephyrus G14 GA401IV_GA401IV"), None).unwrap();
+        assert_eq!(selected.entry.layout, "ga401");
+    }
+
+    #[test]
+    fn matches_dmi_model_case_insensitively() {
+        let selected = select_hardware(Some("rog strix g634jyr_g634jyr"), None).unwrap();
+
+        assert_eq!(selected.entry.layout, "g634jy");
+    }
+
+    #[test]
+    fn selects_layout_from_touchpad_id_without_dmi() {
+        let selected = select_hardware(None, Some(G634JY_ID)).unwrap();
+
+        assert_eq!(selected.entry.layout, "g634jy");
+        assert_eq!(selected.entry.i2c_address, 0x38);
+        assert!(!selected.matched_dmi);
+        assert!(selected.matched_touchpad);
+    }
+
+    #[test]
+    fn prefers_dmi_match_over_touchpad_id_match() {
+        let selected = select_hardware(Some("VivoBook M433IA_M433IA"), Some(G634JY_ID)).unwrap();
+
+        assert_eq!(selected.entry.layout, "m433ia");
+    }
+
+    #[test]
+    fn returns_none_for_unknown_hardware() {
+        let unknown = DeviceId {
+            vendor: 0x04f3,
+            product: 0x0001,
+        };
+
+        assert_eq!(
+            select_hardware(Some("Standard PC (Q35)"), Some(unknown)),
+            None
+        );
+        assert_eq!(select_hardware(None, None), None);
+    }
+}
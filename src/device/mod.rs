@@ -1,3 +1,5 @@
 mod detection;
+mod hardware;
 
-pub use detection::detect_devices;
+pub use detection::{detect_devices, DeviceId, InputDeviceInfo};
+pub use hardware::{read_product_name, select_hardware, AUTO_MODEL, HARDWARE_TABLE};
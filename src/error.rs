@@ -5,11 +5,28 @@ pub enum DriverError {
     #[error("Failed to parse device info: {0}")]
     ParseError(String),
 
-    #[error("Layout not found: {0}")]
-    LayoutNotFound(String),
+    #[error("Layout not found: {name}{}", did_you_mean(.suggestions))]
+    LayoutNotFound {
+        name: String,
+        suggestions: Vec<String>,
+    },
+
+    #[error("Invalid layout: {0}")]
+    InvalidLayout(String),
+
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
 
     #[error("Device detection timed out after {0} attempts")]
     DetectionTimeout(u32),
 }
 
+fn did_you_mean(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!(" (did you mean {}?)", suggestions.join(", "))
+    }
+}
+
 pub type Result<T> = std::result::Result<T, DriverError>;
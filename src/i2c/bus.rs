@@ -0,0 +1,47 @@
+use i2c_linux::{I2c, Message, WriteFlags};
+use std::fs::File;
+use std::io;
+
+/// Raw writes to a device on an I2C bus
+pub trait I2cBus {
+    fn write(&mut self, address: u16, data: &[u8]) -> io::Result<()>;
+}
+
+impl I2cBus for I2c<File> {
+    fn write(&mut self, address: u16, data: &[u8]) -> io::Result<()> {
+        self.smbus_set_slave_address(address, false)?;
+        let mut messages = [Message::Write {
+            address,
+            data,
+            flags: WriteFlags::default(),
+        }];
+        self.i2c_transfer(&mut messages)
+    }
+}
+
+/// Address and bytes of one write
+#[cfg(test)]
+type Write = (u16, Vec<u8>);
+
+/// Bus that keeps every write for tests to inspect; clones share the writes
+#[cfg(test)]
+#[derive(Debug, Clone, Default)]
+pub struct RecordingBus {
+    writes: std::rc::Rc<std::cell::RefCell<Vec<Write>>>,
+}
+
+#[cfg(test)]
+impl RecordingBus {
+    /// Each write so far, oldest first
+    pub fn writes(&self) -> Vec<Write> {
+        self.writes.borrow().clone()
+    }
+}
+
+#[cfg(test)]
+impl I2cBus for RecordingBus {
+    fn write(&mut self, address: u16, data: &[u8]) -> io::Result<()> {
+        self.writes.borrow_mut().push((address, data.to_vec()));
+        Ok(())
+    }
+}
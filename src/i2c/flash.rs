@@ -0,0 +1,200 @@
+use super::{Brightness, LedController};
+use log::warn;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+/// When and how long the backlight flashes as touch feedback
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FlashSettings {
+    /// Dim the backlight for a moment on each key sent
+    #[serde(default = "default_true")]
+    pub keys: bool,
+    /// Blink the backlight off for touches that hit no key
+    #[serde(default = "default_true")]
+    pub dead_zones: bool,
+    /// How long the backlight stays dimmed or off
+    #[serde(default = "default_duration_ms")]
+    pub duration_ms: u64,
+    /// Shortest time between the starts of two flashes, which caps the I2C
+    /// writes while typing fast
+    #[serde(default = "default_min_interval_ms")]
+    pub min_interval_ms: u64,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_duration_ms() -> u64 {
+    60
+}
+
+fn default_min_interval_ms() -> u64 {
+    150
+}
+
+impl Default for FlashSettings {
+    fn default() -> Self {
+        Self {
+            keys: true,
+            dead_zones: true,
+            duration_ms: default_duration_ms(),
+            min_interval_ms: default_min_interval_ms(),
+        }
+    }
+}
+
+impl FlashSettings {
+    pub fn check(&self) -> Result<(), String> {
+        if !(1..=1000).contains(&self.duration_ms) {
+            return Err(format!(
+                "flash duration_ms must be in 1..=1000, got {}",
+                self.duration_ms
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// What a flash confirms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlashKind {
+    /// A key or action was sent
+    Key,
+    /// A touch on the numpad hit no key
+    DeadZone,
+}
+
+/// Pulses the backlight away from its level and back
+#[derive(Debug)]
+pub struct LedFlash {
+    settings: FlashSettings,
+    /// When the current pulse ends
+    restore_at: Option<Instant>,
+    last_flash: Option<Instant>,
+}
+
+impl LedFlash {
+    pub fn new(settings: FlashSettings) -> Self {
+        Self {
+            settings,
+            restore_at: None,
+            last_flash: None,
+        }
+    }
+
+    /// Start a pulse away from `level` unless this kind is off, a pulse is
+    /// running or the last one started too recently
+    pub fn flash(
+        &mut self,
+        kind: FlashKind,
+        led: &mut LedController,
+        level: Brightness,
+        now: Instant,
+    ) {
+        let enabled = match kind {
+            FlashKind::Key => self.settings.keys,
+            FlashKind::DeadZone => self.settings.dead_zones,
+        };
+        let min_interval = Duration::from_millis(self.settings.min_interval_ms);
+        if !enabled
+            || self.restore_at.is_some()
+            || self
+                .last_flash
+                .is_some_and(|last| now.saturating_duration_since(last) < min_interval)
+        {
+            return;
+        }
+
+        let pulse = match kind {
+            FlashKind::Key => level.dimmer(),
+            FlashKind::DeadZone => Brightness::Off,
+        };
+        if let Err(e) = led.set_brightness(pulse) {
+            warn!("Failed to flash LED: {}", e);
+            return;
+        }
+        self.last_flash = Some(now);
+        self.restore_at = Some(now + Duration::from_millis(self.settings.duration_ms));
+    }
+
+    /// When the running pulse ends, if one is running
+    pub fn next_due(&self) -> Option<Instant> {
+        self.restore_at
+    }
+
+    /// End the running pulse at `now` if it is due, returning to `level`
+    pub fn restore(&mut self, led: &mut LedController, level: Brightness, now: Instant) {
+        if self.restore_at.is_some_and(|at| at <= now) {
+            self.restore_at = None;
+            if let Err(e) = led.set_brightness(level) {
+                warn!("Failed to restore LED brightness: {}", e);
+            }
+        }
+    }
+
+    /// Forget the running pulse, e.g. when the backlight is turned off
+    pub fn cancel(&mut self) {
+        self.restore_at = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i2c::RecordingBus;
+
+    #[test]
+    fn pulses_and_restores_at_a_limited_rate() {
+        let bus = RecordingBus::default();
+        let mut led = LedController::with_bus(Box::new(bus.clone()), 0x38);
+        let mut flash = LedFlash::new(FlashSettings::default());
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        flash.flash(FlashKind::Key, &mut led, Brightness::High, start);
+        assert_eq!(flash.next_due(), Some(at(60)));
+        // A key during the pulse does not extend it
+        flash.flash(FlashKind::Key, &mut led, Brightness::High, at(30));
+        flash.restore(&mut led, Brightness::High, at(59));
+        flash.restore(&mut led, Brightness::High, at(60));
+        assert_eq!(flash.next_due(), None);
+        // Too soon after the first
+        flash.flash(FlashKind::Key, &mut led, Brightness::High, at(100));
+        flash.flash(FlashKind::DeadZone, &mut led, Brightness::Low, at(150));
+        flash.restore(&mut led, Brightness::Low, at(210));
+        flash.flash(FlashKind::Key, &mut led, Brightness::Low, at(300));
+        // Turned off mid-pulse, so nothing is restored
+        flash.cancel();
+        flash.restore(&mut led, Brightness::Low, at(400));
+
+        let levels: Vec<u8> = bus
+            .writes()
+            .into_iter()
+            .map(|(address, command)| {
+                assert_eq!(address, 0x38);
+                command[11]
+            })
+            .collect();
+        assert_eq!(
+            levels,
+            vec![
+                Brightness::Medium.as_byte(),
+                Brightness::High.as_byte(),
+                Brightness::Off.as_byte(),
+                Brightness::Low.as_byte(),
+                Brightness::Off.as_byte(),
+            ]
+        );
+
+        let quiet = FlashSettings {
+            keys: false,
+            ..FlashSettings::default()
+        };
+        let mut flash = LedFlash::new(quiet);
+        flash.flash(FlashKind::Key, &mut led, Brightness::High, at(1000));
+        assert_eq!(flash.next_due(), None);
+        assert_eq!(bus.writes().len(), 5);
+    }
+}
@@ -1,6 +1,6 @@
+use super::I2cBus;
 use i2c_linux::I2c;
 use log::{debug, warn};
-use std::fs::File;
 use std::io;
 
 /// Brightness levels matching the Python driver
@@ -23,6 +23,13 @@ impl Brightness {
         }
     }
 
+    /// Level for a position along a brightness slider, dimmest at 0.0
+    pub fn from_fraction(fraction: f64) -> Self {
+        const LEVELS: [Brightness; 3] = [Brightness::Low, Brightness::Medium, Brightness::High];
+        let index = (fraction * LEVELS.len() as f64) as usize;
+        LEVELS[index.min(LEVELS.len() - 1)]
+    }
+
     /// Cycle to next brightness level
     pub fn next(self) -> Self {
         match self {
@@ -32,11 +39,20 @@ impl Brightness {
             Brightness::Off => Brightness::Low,
         }
     }
+
+    /// One level dimmer, down to off
+    pub fn dimmer(self) -> Self {
+        match self {
+            Brightness::High => Brightness::Medium,
+            Brightness::Medium => Brightness::Low,
+            Brightness::Low | Brightness::Off => Brightness::Off,
+        }
+    }
 }
 
 /// I2C LED controller for touchpad backlight
 pub struct LedController {
-    i2c: I2c<File>,
+    bus: Box<dyn I2cBus>,
     address: u16,
 }
 
@@ -46,10 +62,15 @@ impl LedController {
         let path = format!("/dev/i2c-{}", bus_number);
         debug!("Opening I2C device: {}", path);
         let i2c = I2c::from_path(&path)?;
-        Ok(Self {
-            i2c,
+        Ok(Self::with_bus(Box::new(i2c), address))
+    }
+
+    /// Create an LED controller writing to an already opened bus
+    pub fn with_bus(bus: Box<dyn I2cBus>, address: u8) -> Self {
+        Self {
+            bus,
             address: address as u16,
-        })
+        }
     }
 
     /// Set LED brightness
@@ -86,18 +107,7 @@ impl LedController {
                 .join(" ")
         );
 
-        // Set the slave address
-        self.i2c.smbus_set_slave_address(self.address, false)?;
-
-        // Write the command using i2c_transfer
-        use i2c_linux::{Message, WriteFlags};
-        let mut messages = [Message::Write {
-            address: self.address,
-            data: &command,
-            flags: WriteFlags::default(),
-        }];
-
-        self.i2c.i2c_transfer(&mut messages)?;
+        self.bus.write(self.address, &command)?;
 
         debug!("LED brightness set successfully");
         Ok(())
@@ -1,3 +1,9 @@
+mod bus;
+mod flash;
 mod led;
 
+pub use bus::I2cBus;
+#[cfg(test)]
+pub use bus::RecordingBus;
+pub use flash::{FlashKind, FlashSettings, LedFlash};
 pub use led::{try_create_led_controller, Brightness, LedController};
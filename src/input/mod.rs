@@ -1,5 +1,7 @@
 mod touchpad;
 mod virtual_keyboard;
+mod virtual_touchpad;
 
-pub use touchpad::{TouchpadBounds, TouchpadReader};
+pub use touchpad::{ContactAxes, PressureRange, TouchpadBounds, TouchpadReader};
 pub use virtual_keyboard::VirtualKeyboard;
+pub use virtual_touchpad::VirtualTouchpad;
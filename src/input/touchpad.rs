@@ -1,7 +1,9 @@
 use evdev::{AbsoluteAxisCode, Device};
 use log::debug;
 use std::io;
+use std::os::fd::AsRawFd;
 use std::path::Path;
+use std::time::Duration;
 
 /// Touchpad dimensions from absinfo
 #[derive(Debug, Clone, Copy)]
@@ -12,10 +14,28 @@ pub struct TouchpadBounds {
     pub max_y: i32,
 }
 
+/// Raw range of the pressure axis from absinfo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PressureRange {
+    pub min: i32,
+    pub max: i32,
+}
+
+/// Per-contact axes the touchpad reports besides position
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContactAxes {
+    pub touch_major: bool,
+    pub touch_minor: bool,
+    pub pressure: bool,
+    pub tool_type: bool,
+}
+
 /// Touchpad input handler
 pub struct TouchpadReader {
     device: Device,
     bounds: TouchpadBounds,
+    contact_axes: ContactAxes,
+    pressure: Option<PressureRange>,
     grabbed: bool,
 }
 
@@ -69,9 +89,42 @@ impl TouchpadReader {
             bounds.min_x, bounds.max_x, bounds.min_y, bounds.max_y
         );
 
+        let supports = |axis| {
+            device
+                .supported_absolute_axes()
+                .is_some_and(|axes| axes.contains(axis))
+        };
+        let contact_axes = ContactAxes {
+            touch_major: supports(AbsoluteAxisCode::ABS_MT_TOUCH_MAJOR),
+            touch_minor: supports(AbsoluteAxisCode::ABS_MT_TOUCH_MINOR),
+            pressure: supports(AbsoluteAxisCode::ABS_MT_PRESSURE),
+            tool_type: supports(AbsoluteAxisCode::ABS_MT_TOOL_TYPE),
+        };
+        debug!("Touchpad contact axes: {:?}", contact_axes);
+
+        // Single-touch pads report the pressure of their one contact in
+        // ABS_PRESSURE; multitouch pads report it per slot
+        let pressure_axis = if contact_axes.pressure {
+            Some(AbsoluteAxisCode::ABS_MT_PRESSURE)
+        } else if x_axis == AbsoluteAxisCode::ABS_X && supports(AbsoluteAxisCode::ABS_PRESSURE) {
+            Some(AbsoluteAxisCode::ABS_PRESSURE)
+        } else {
+            None
+        };
+        let pressure = pressure_axis
+            .map(|axis| &abs_state[axis.0 as usize])
+            .filter(|info| info.maximum > info.minimum)
+            .map(|info| PressureRange {
+                min: info.minimum,
+                max: info.maximum,
+            });
+        debug!("Touchpad pressure range: {:?}", pressure);
+
         Ok(Self {
             device,
             bounds,
+            contact_axes,
+            pressure,
             grabbed: false,
         })
     }
@@ -80,6 +133,21 @@ impl TouchpadReader {
         self.bounds
     }
 
+    /// Size, pressure and tool type axes available for palm rejection
+    pub fn contact_axes(&self) -> ContactAxes {
+        self.contact_axes
+    }
+
+    /// Range of the pressure reported for each contact, if any
+    pub fn pressure_range(&self) -> Option<PressureRange> {
+        self.pressure
+    }
+
+    /// Underlying device, for mirroring its capabilities
+    pub(super) fn device(&self) -> &Device {
+        &self.device
+    }
+
     /// Grab exclusive access to the touchpad
     pub fn grab(&mut self) -> io::Result<()> {
         if !self.grabbed {
@@ -104,6 +172,21 @@ impl TouchpadReader {
         Ok(())
     }
 
+    /// Wait up to `timeout` for events; returns true when some are ready
+    pub fn poll(&self, timeout: Duration) -> io::Result<bool> {
+        let mut fd = libc::pollfd {
+            fd: self.device.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // Round up so a wait never ends just short of a deadline
+        let timeout_ms = i32::try_from(timeout.as_micros().div_ceil(1000)).unwrap_or(i32::MAX);
+        match unsafe { libc::poll(&mut fd, 1, timeout_ms) } {
+            -1 => Err(io::Error::last_os_error()),
+            ready => Ok(ready > 0),
+        }
+    }
+
     /// Fetch events and collect them into a Vec to avoid borrow issues
     pub fn fetch_events(&mut self) -> io::Result<Vec<evdev::InputEvent>> {
         self.device
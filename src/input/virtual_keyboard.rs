@@ -1,3 +1,4 @@
+use crate::layouts::KeyStroke;
 use evdev::uinput::VirtualDevice;
 use evdev::{AttributeSet, InputEvent, KeyCode, SynchronizationCode};
 use log::debug;
@@ -70,6 +71,37 @@ impl VirtualKeyboard {
         self.device.emit(&events)
     }
 
+    /// Tap each stroke in turn, holding its modifiers around the key
+    pub fn type_strokes(&mut self, strokes: &[KeyStroke]) -> io::Result<()> {
+        for stroke in strokes {
+            let mut events = Vec::with_capacity(2 * stroke.modifiers.len() + 3);
+            for modifier in &stroke.modifiers {
+                events.push(InputEvent::new_now(evdev::EventType::KEY.0, modifier.0, 1));
+            }
+            events.push(InputEvent::new_now(
+                evdev::EventType::KEY.0,
+                stroke.key.0,
+                1,
+            ));
+            events.push(InputEvent::new_now(
+                evdev::EventType::SYNCHRONIZATION.0,
+                SynchronizationCode::SYN_REPORT.0,
+                0,
+            ));
+            events.push(InputEvent::new_now(
+                evdev::EventType::KEY.0,
+                stroke.key.0,
+                0,
+            ));
+            for modifier in stroke.modifiers.iter().rev() {
+                events.push(InputEvent::new_now(evdev::EventType::KEY.0, modifier.0, 0));
+            }
+            // The release is closed by the SYN_REPORT the device appends
+            self.device.emit(&events)?;
+        }
+        Ok(())
+    }
+
     /// Send a full NumLock key click
     pub fn click_numlock(&mut self) -> io::Result<()> {
         self.click_key(KeyCode::KEY_NUMLOCK)
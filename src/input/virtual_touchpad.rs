@@ -0,0 +1,53 @@
+use super::TouchpadReader;
+use evdev::uinput::VirtualDevice;
+use evdev::{EventType, InputEvent, SynchronizationCode, UinputAbsSetup};
+use log::debug;
+use std::io;
+
+/// Copy of the real touchpad that receives pointer input while the numpad
+/// has the real one grabbed
+pub struct VirtualTouchpad {
+    device: VirtualDevice,
+}
+
+impl VirtualTouchpad {
+    /// Create a uinput touchpad with the same buttons, axes, absinfo and
+    /// properties as `touchpad`
+    pub fn new(touchpad: &TouchpadReader) -> io::Result<Self> {
+        let source = touchpad.device();
+        let mut builder = VirtualDevice::builder()?
+            .name("Asus Touchpad/Numpad Pointer")
+            .input_id(source.input_id())
+            .with_properties(source.properties())?;
+        if let Some(keys) = source.supported_keys() {
+            builder = builder.with_keys(keys)?;
+        }
+        for (axis, info) in source.get_absinfo()? {
+            builder = builder.with_absolute_axis(&UinputAbsSetup::new(axis, info))?;
+        }
+
+        let device = builder.build()?;
+        debug!("Created virtual touchpad device");
+        Ok(Self { device })
+    }
+
+    /// Replay touchpad frames on the virtual device
+    ///
+    /// The `SYN_REPORT`s between frames are kept; the closing one is dropped
+    /// because the device appends its own.
+    pub fn emit(&mut self, events: &[InputEvent]) -> io::Result<()> {
+        let events = match events.split_last() {
+            Some((last, rest))
+                if last.event_type() == EventType::SYNCHRONIZATION
+                    && SynchronizationCode(last.code()) == SynchronizationCode::SYN_REPORT =>
+            {
+                rest
+            }
+            _ => events,
+        };
+        if events.is_empty() {
+            return Ok(());
+        }
+        self.device.emit(events)
+    }
+}
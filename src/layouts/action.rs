@@ -0,0 +1,301 @@
+use evdev::KeyCode;
+use serde::Deserialize;
+use std::str::FromStr;
+
+/// Key tapped while modifiers are held, e.g. Ctrl+C
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyStroke {
+    pub modifiers: Vec<KeyCode>,
+    pub key: KeyCode,
+}
+
+impl KeyStroke {
+    pub fn new(modifiers: &[KeyCode], key: KeyCode) -> Self {
+        Self {
+            modifiers: modifiers.to_vec(),
+            key,
+        }
+    }
+}
+
+/// What touching a numpad key does
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Key held down until the finger lifts
+    Key(KeyCode),
+    /// Modifier combo tapped on touch
+    Combo(KeyStroke),
+    /// Text typed as a sequence of key strokes on touch
+    Text(String),
+    /// Shell command spawned on touch
+    Command(String),
+}
+
+impl Action {
+    /// Keys the virtual keyboard must register to perform this action
+    pub fn keys(&self) -> Vec<KeyCode> {
+        let mut keys = Vec::new();
+        for stroke in self.strokes() {
+            for key in stroke.modifiers.iter().chain([&stroke.key]) {
+                if !keys.contains(key) {
+                    keys.push(*key);
+                }
+            }
+        }
+        keys
+    }
+
+    /// Key strokes this action types; empty for commands
+    pub fn strokes(&self) -> Vec<KeyStroke> {
+        match self {
+            Action::Key(key) => vec![KeyStroke::new(&[], *key)],
+            Action::Combo(stroke) => vec![stroke.clone()],
+            Action::Text(text) => text.chars().flat_map(char_strokes).collect(),
+            Action::Command(_) => Vec::new(),
+        }
+    }
+}
+
+/// Action as written in layout and configuration files
+///
+/// `{ key = "KEY_ESC" }`, `{ combo = "Ctrl+C" }`, `{ text = "00" }` or
+/// `{ command = "gnome-calculator" }`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "lowercase", deny_unknown_fields)]
+pub enum ActionDefinition {
+    Key(String),
+    Combo(String),
+    Text(String),
+    Command(String),
+}
+
+impl ActionDefinition {
+    /// Resolve key names; the error describes the offending part
+    pub fn parse(&self) -> std::result::Result<Action, String> {
+        match self {
+            ActionDefinition::Key(name) => parse_key_name(name).map(Action::Key),
+            ActionDefinition::Combo(combo) => parse_combo(combo).map(Action::Combo),
+            ActionDefinition::Text(text) => {
+                if text.is_empty() {
+                    return Err("text action is empty".to_string());
+                }
+                if let Some(c) = text
+                    .chars()
+                    .find(|c| c.is_control() && !matches!(c, '\n' | '\t'))
+                {
+                    return Err(format!("text action cannot type control character {:?}", c));
+                }
+                Ok(Action::Text(text.clone()))
+            }
+            ActionDefinition::Command(command) => {
+                if command.trim().is_empty() {
+                    return Err("command action is empty".to_string());
+                }
+                Ok(Action::Command(command.clone()))
+            }
+        }
+    }
+}
+
+/// Parse `Ctrl+Shift+Tab` or `KEY_LEFTCTRL+KEY_C`; the last part is the key
+pub fn parse_combo(combo: &str) -> std::result::Result<KeyStroke, String> {
+    let mut keys = combo
+        .split('+')
+        .map(|part| parse_key_name(part.trim()))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    match keys.pop() {
+        Some(key) if !keys.is_empty() => Ok(KeyStroke {
+            modifiers: keys,
+            key,
+        }),
+        _ => Err(format!(
+            "combo {:?} needs at least one modifier and a key, e.g. Ctrl+C",
+            combo
+        )),
+    }
+}
+
+/// Evdev name (`KEY_C`), short name (`C`, `Tab`) or modifier alias (`Ctrl`)
+fn parse_key_name(name: &str) -> std::result::Result<KeyCode, String> {
+    let alias = match name.to_ascii_lowercase().as_str() {
+        "ctrl" | "control" => Some(KeyCode::KEY_LEFTCTRL),
+        "shift" => Some(KeyCode::KEY_LEFTSHIFT),
+        "alt" => Some(KeyCode::KEY_LEFTALT),
+        "altgr" => Some(KeyCode::KEY_RIGHTALT),
+        "super" | "meta" | "win" => Some(KeyCode::KEY_LEFTMETA),
+        _ => None,
+    };
+    alias
+        .or_else(|| KeyCode::from_str(name).ok())
+        .or_else(|| KeyCode::from_str(&format!("KEY_{}", name.to_ascii_uppercase())).ok())
+        .ok_or_else(|| format!("unknown key name {:?}", name))
+}
+
+/// Strokes typing one character on a US keyboard layout
+///
+/// Characters without a US key are entered as Ctrl+Shift+U, the hex code
+/// point and Space, which GTK and IBus accept as Unicode input.
+fn char_strokes(c: char) -> Vec<KeyStroke> {
+    const SHIFT: &[KeyCode] = &[KeyCode::KEY_LEFTSHIFT];
+    let key = |name: String| KeyCode::from_str(&name).ok();
+
+    let stroke = match c {
+        'a'..='z' | '0'..='9' => {
+            key(format!("KEY_{}", c.to_ascii_uppercase())).map(|k| KeyStroke::new(&[], k))
+        }
+        'A'..='Z' => key(format!("KEY_{}", c)).map(|k| KeyStroke::new(SHIFT, k)),
+        _ => {
+            let (shift, key) = match c {
+                ' ' => (false, KeyCode::KEY_SPACE),
+                '\n' => (false, KeyCode::KEY_ENTER),
+                '\t' => (false, KeyCode::KEY_TAB),
+                '-' => (false, KeyCode::KEY_MINUS),
+                '=' => (false, KeyCode::KEY_EQUAL),
+                '[' => (false, KeyCode::KEY_LEFTBRACE),
+                ']' => (false, KeyCode::KEY_RIGHTBRACE),
+                '\\' => (false, KeyCode::KEY_BACKSLASH),
+                ';' => (false, KeyCode::KEY_SEMICOLON),
+                '\'' => (false, KeyCode::KEY_APOSTROPHE),
+                '`' => (false, KeyCode::KEY_GRAVE),
+                ',' => (false, KeyCode::KEY_COMMA),
+                '.' => (false, KeyCode::KEY_DOT),
+                '/' => (false, KeyCode::KEY_SLASH),
+                '!' => (true, KeyCode::KEY_1),
+                '@' => (true, KeyCode::KEY_2),
+                '#' => (true, KeyCode::KEY_3),
+                '$' => (true, KeyCode::KEY_4),
+                '%' => (true, KeyCode::KEY_5),
+                '^' => (true, KeyCode::KEY_6),
+                '&' => (true, KeyCode::KEY_7),
+                '*' => (true, KeyCode::KEY_8),
+                '(' => (true, KeyCode::KEY_9),
+                ')' => (true, KeyCode::KEY_0),
+                '_' => (true, KeyCode::KEY_MINUS),
+                '+' => (true, KeyCode::KEY_EQUAL),
+                '{' => (true, KeyCode::KEY_LEFTBRACE),
+                '}' => (true, KeyCode::KEY_RIGHTBRACE),
+                '|' => (true, KeyCode::KEY_BACKSLASH),
+                ':' => (true, KeyCode::KEY_SEMICOLON),
+                '"' => (true, KeyCode::KEY_APOSTROPHE),
+                '~' => (true, KeyCode::KEY_GRAVE),
+                '<' => (true, KeyCode::KEY_COMMA),
+                '>' => (true, KeyCode::KEY_DOT),
+                '?' => (true, KeyCode::KEY_SLASH),
+                _ => return unicode_strokes(c),
+            };
+            Some(KeyStroke::new(if shift { SHIFT } else { &[] }, key))
+        }
+    };
+    stroke.into_iter().collect()
+}
+
+fn unicode_strokes(c: char) -> Vec<KeyStroke> {
+    let mut strokes = vec![KeyStroke::new(
+        &[KeyCode::KEY_LEFTCTRL, KeyCode::KEY_LEFTSHIFT],
+        KeyCode::KEY_U,
+    )];
+    for digit in format!("{:x}", c as u32).chars() {
+        strokes.extend(char_strokes(digit));
+    }
+    strokes.push(KeyStroke::new(&[], KeyCode::KEY_SPACE));
+    strokes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_combos_with_aliases_and_evdev_names() {
+        assert_eq!(
+            parse_combo("Ctrl+C"),
+            Ok(KeyStroke::new(&[KeyCode::KEY_LEFTCTRL], KeyCode::KEY_C))
+        );
+        assert_eq!(
+            parse_combo("shift + Tab"),
+            Ok(KeyStroke::new(&[KeyCode::KEY_LEFTSHIFT], KeyCode::KEY_TAB))
+        );
+        assert_eq!(
+            parse_combo("KEY_LEFTCTRL+KEY_LEFTALT+KEY_DELETE"),
+            Ok(KeyStroke::new(
+                &[KeyCode::KEY_LEFTCTRL, KeyCode::KEY_LEFTALT],
+                KeyCode::KEY_DELETE
+            ))
+        );
+        assert!(parse_combo("Ctrl+Nope").unwrap_err().contains("\"Nope\""));
+        assert!(parse_combo("KEY_C").is_err());
+    }
+
+    #[test]
+    fn text_is_typed_with_us_key_strokes() {
+        let action = Action::Text("0A%".to_string());
+
+        assert_eq!(
+            action.strokes(),
+            vec![
+                KeyStroke::new(&[], KeyCode::KEY_0),
+                KeyStroke::new(&[KeyCode::KEY_LEFTSHIFT], KeyCode::KEY_A),
+                KeyStroke::new(&[KeyCode::KEY_LEFTSHIFT], KeyCode::KEY_5),
+            ]
+        );
+        assert_eq!(
+            action.keys(),
+            vec![
+                KeyCode::KEY_0,
+                KeyCode::KEY_LEFTSHIFT,
+                KeyCode::KEY_A,
+                KeyCode::KEY_5
+            ]
+        );
+    }
+
+    #[test]
+    fn non_ascii_text_uses_unicode_entry() {
+        let strokes = Action::Text("€".to_string()).strokes();
+
+        assert_eq!(
+            strokes[0],
+            KeyStroke::new(
+                &[KeyCode::KEY_LEFTCTRL, KeyCode::KEY_LEFTSHIFT],
+                KeyCode::KEY_U
+            )
+        );
+        let digits: Vec<KeyCode> = strokes[1..5].iter().map(|s| s.key).collect();
+        assert_eq!(
+            digits,
+            [
+                KeyCode::KEY_2,
+                KeyCode::KEY_0,
+                KeyCode::KEY_A,
+                KeyCode::KEY_C
+            ]
+        );
+        assert_eq!(strokes[5], KeyStroke::new(&[], KeyCode::KEY_SPACE));
+    }
+
+    #[test]
+    fn parses_action_definitions() {
+        let parse = |source: &str| {
+            #[derive(Deserialize)]
+            struct Wrapper {
+                action: ActionDefinition,
+            }
+            toml::from_str::<Wrapper>(source).unwrap().action.parse()
+        };
+
+        assert_eq!(
+            parse(r#"action = { key = "KEY_ESC" }"#),
+            Ok(Action::Key(KeyCode::KEY_ESC))
+        );
+        assert_eq!(
+            parse(r#"action = { text = "00" }"#),
+            Ok(Action::Text("00".to_string()))
+        );
+        assert_eq!(
+            parse(r#"action = { command = "gnome-calculator" }"#),
+            Ok(Action::Command("gnome-calculator".to_string()))
+        );
+        assert!(parse(r#"action = { text = "" }"#).is_err());
+        assert!(parse(r#"action = { key = "KEY_NOPE" }"#).is_err());
+    }
+}
@@ -0,0 +1,265 @@
+use super::Band;
+use evdev::KeyCode;
+use std::fmt::{self, Write};
+
+/// Something the user taps during calibration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationTarget {
+    Toggle,
+    Calculator,
+    Key(KeyCode),
+}
+
+impl fmt::Display for CalibrationTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalibrationTarget::Toggle => write!(f, "the numpad toggle icon"),
+            CalibrationTarget::Calculator => write!(f, "the calculator icon"),
+            CalibrationTarget::Key(key) => write!(f, "{:?}", key),
+        }
+    }
+}
+
+/// Narrowest hitbox side, so a target tapped at a single spot still gets an area
+const MIN_HITBOX_SIZE: f64 = 0.01;
+
+/// Hitbox computed for one target
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CalibratedHitbox {
+    pub target: CalibrationTarget,
+    pub x: Band,
+    pub y: Band,
+}
+
+/// Compute hitboxes from normalized tap positions
+///
+/// Each hitbox is the bounding box of its taps grown by `margin`, clamped to
+/// the pad and widened to [`MIN_HITBOX_SIZE`] where it is narrower. Where two hitboxes then overlap, they are split at the middle
+/// of the overlap along the axis where they overlap least. Targets without
+/// taps are skipped.
+pub fn calibrated_hitboxes(
+    taps: &[(CalibrationTarget, Vec<(f64, f64)>)],
+    margin: f64,
+) -> Vec<CalibratedHitbox> {
+    let mut hitboxes: Vec<CalibratedHitbox> = taps
+        .iter()
+        .filter_map(|(target, points)| {
+            let points: Vec<&(f64, f64)> = points
+                .iter()
+                .filter(|(x, y)| (0.0..=1.0).contains(x) && (0.0..=1.0).contains(y))
+                .collect();
+            let band = |axis: fn(&(f64, f64)) -> f64| -> Option<Band> {
+                let lo = points.iter().map(|p| axis(p)).reduce(f64::min)?;
+                let hi = points.iter().map(|p| axis(p)).reduce(f64::max)?;
+                Some(widen(((lo - margin).max(0.0), (hi + margin).min(1.0))))
+            };
+            Some(CalibratedHitbox {
+                target: *target,
+                x: band(|p| p.0)?,
+                y: band(|p| p.1)?,
+            })
+        })
+        .collect();
+
+    for i in 0..hitboxes.len() {
+        for j in i + 1..hitboxes.len() {
+            let (head, tail) = hitboxes.split_at_mut(j);
+            separate(&mut head[i], &mut tail[0]);
+        }
+    }
+    hitboxes
+}
+
+/// Grow a band narrower than [`MIN_HITBOX_SIZE`] around its centre, staying on the pad
+fn widen((start, end): Band) -> Band {
+    if end - start >= MIN_HITBOX_SIZE {
+        return (start, end);
+    }
+    let half = MIN_HITBOX_SIZE / 2.0;
+    let centre = ((start + end) / 2.0).clamp(half, 1.0 - half);
+    (centre - half, centre + half)
+}
+
+/// Shrink two overlapping hitboxes so they only touch
+fn separate(a: &mut CalibratedHitbox, b: &mut CalibratedHitbox) {
+    let overlap = |p: Band, q: Band| p.1.min(q.1) - p.0.max(q.0);
+    let (overlap_x, overlap_y) = (overlap(a.x, b.x), overlap(a.y, b.y));
+    if overlap_x <= 0.0 || overlap_y <= 0.0 {
+        return;
+    }
+
+    let split = |p: &mut Band, q: &mut Band| {
+        let mid = (p.0.max(q.0) + p.1.min(q.1)) / 2.0;
+        if p.0 + p.1 <= q.0 + q.1 {
+            p.1 = mid;
+            q.0 = mid;
+        } else {
+            q.1 = mid;
+            p.0 = mid;
+        }
+    };
+    if overlap_x <= overlap_y {
+        split(&mut a.x, &mut b.x);
+    } else {
+        split(&mut a.y, &mut b.y);
+    }
+}
+
+/// Write calibrated hitboxes as a TOML layout definition
+pub fn calibrated_layout_toml(name: &str, hitboxes: &[CalibratedHitbox]) -> String {
+    let band = |(start, end): Band| format!("[{:.3}, {:.3}]", start, end);
+    let mut out = String::new();
+    let _ = writeln!(out, "# Generated by `asus-rog-touchpad-numpad calibrate`");
+    let _ = writeln!(out, "name = {:?}", name);
+
+    for hitbox in hitboxes {
+        let _ = writeln!(out);
+        match hitbox.target {
+            CalibrationTarget::Toggle => {
+                let _ = writeln!(out, "[toggle]");
+            }
+            CalibrationTarget::Calculator => {
+                let _ = writeln!(out, "[calculator]");
+            }
+            CalibrationTarget::Key(key) => {
+                let _ = writeln!(out, "[[keys]]");
+                let _ = writeln!(out, "key = \"{:?}\"", key);
+            }
+        }
+        let _ = writeln!(out, "x = {}", band(hitbox.x));
+        let _ = writeln!(out, "y = {}", band(hitbox.y));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::{zone_action_at, ZoneAction};
+    use crate::layouts::{FileLayout, NumpadLayout};
+
+    /// Centre and four corners of a key spanning the given bands
+    fn corner_taps(x: Band, y: Band) -> Vec<(f64, f64)> {
+        vec![
+            ((x.0 + x.1) / 2.0, (y.0 + y.1) / 2.0),
+            (x.0, y.0),
+            (x.1, y.0),
+            (x.0, y.1),
+            (x.1, y.1),
+        ]
+    }
+
+    #[test]
+    fn hitbox_is_padded_bounding_box_of_taps() {
+        let hitboxes = calibrated_hitboxes(
+            &[(
+                CalibrationTarget::Key(KeyCode::KEY_KP5),
+                corner_taps((0.30, 0.40), (0.40, 0.50)),
+            )],
+            0.01,
+        );
+
+        assert_eq!(hitboxes.len(), 1);
+        assert!((hitboxes[0].x.0 - 0.29).abs() < 1e-9);
+        assert!((hitboxes[0].x.1 - 0.41).abs() < 1e-9);
+        assert!((hitboxes[0].y.0 - 0.39).abs() < 1e-9);
+        assert!((hitboxes[0].y.1 - 0.51).abs() < 1e-9);
+    }
+
+    #[test]
+    fn margins_are_clamped_and_untapped_targets_skipped() {
+        let hitboxes = calibrated_hitboxes(
+            &[
+                (CalibrationTarget::Toggle, vec![(0.97, 0.02), (0.99, 0.05)]),
+                (CalibrationTarget::Calculator, Vec::new()),
+                // Out-of-range samples are ignored
+                (CalibrationTarget::Key(KeyCode::KEY_KP1), vec![(1.5, 0.5)]),
+            ],
+            0.05,
+        );
+
+        assert_eq!(hitboxes.len(), 1);
+        assert_eq!(hitboxes[0].target, CalibrationTarget::Toggle);
+        assert_eq!(hitboxes[0].x.1, 1.0);
+        assert_eq!(hitboxes[0].y.0, 0.0);
+    }
+
+    #[test]
+    fn single_taps_without_margin_still_load() {
+        let hitboxes = calibrated_hitboxes(
+            &[
+                (CalibrationTarget::Toggle, vec![(1.0, 0.0)]),
+                (
+                    CalibrationTarget::Key(KeyCode::KEY_KP5),
+                    vec![(0.40, 0.50), (0.40, 0.50)],
+                ),
+            ],
+            0.0,
+        );
+
+        assert_eq!(hitboxes[0].x, (0.99, 1.0));
+        assert!((hitboxes[1].x.0 - 0.395).abs() < 1e-9);
+        assert!((hitboxes[1].x.1 - 0.405).abs() < 1e-9);
+        FileLayout::from_toml(&calibrated_layout_toml("spots", &hitboxes)).unwrap();
+    }
+
+    #[test]
+    fn overlapping_neighbours_are_split_in_the_middle() {
+        let hitboxes = calibrated_hitboxes(
+            &[
+                (
+                    CalibrationTarget::Key(KeyCode::KEY_KP1),
+                    corner_taps((0.10, 0.30), (0.50, 0.70)),
+                ),
+                (
+                    CalibrationTarget::Key(KeyCode::KEY_KP2),
+                    corner_taps((0.32, 0.50), (0.50, 0.70)),
+                ),
+            ],
+            0.02,
+        );
+
+        assert!((hitboxes[0].x.1 - 0.31).abs() < 1e-9);
+        assert!((hitboxes[1].x.0 - 0.31).abs() < 1e-9);
+        // The other axis is left alone
+        assert_eq!(hitboxes[0].y, hitboxes[1].y);
+    }
+
+    #[test]
+    fn generated_toml_loads_as_layout() {
+        let hitboxes = calibrated_hitboxes(
+            &[
+                (CalibrationTarget::Toggle, vec![(0.96, 0.03)]),
+                (CalibrationTarget::Calculator, vec![(0.03, 0.03)]),
+                (
+                    CalibrationTarget::Key(KeyCode::KEY_KP7),
+                    corner_taps((0.05, 0.45), (0.10, 0.50)),
+                ),
+                (
+                    CalibrationTarget::Key(KeyCode::KEY_KPENTER),
+                    corner_taps((0.55, 0.90), (0.10, 0.90)),
+                ),
+            ],
+            0.02,
+        );
+        let toml = calibrated_layout_toml("my-laptop", &hitboxes);
+        let layout = FileLayout::from_toml(&toml).unwrap();
+
+        assert_eq!(layout.name(), "my-laptop");
+        assert_eq!(
+            zone_action_at(&layout, 0.96, 0.03),
+            Some(ZoneAction::Toggle)
+        );
+        assert_eq!(
+            zone_action_at(&layout, 0.03, 0.03),
+            Some(ZoneAction::Calculator)
+        );
+        assert_ne!(zone_action_at(&layout, 0.5, 0.5), Some(ZoneAction::Toggle));
+        assert_eq!(layout.key_at_position(0.25, 0.30), Some(KeyCode::KEY_KP7));
+        assert_eq!(
+            layout.key_at_position(0.70, 0.50),
+            Some(KeyCode::KEY_KPENTER)
+        );
+        assert_eq!(layout.key_at_position(0.50, 0.95), None);
+    }
+}
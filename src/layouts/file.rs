@@ -0,0 +1,933 @@
+use super::{
+    default_calculator_zone, default_toggle_zone, grid_regions, key_name, uniform_bands,
+    ActionDefinition, Band, ControlZone, HitboxTolerance, KeyLayer, KeyRegion, NumpadLayout,
+    Region, SliderAxis, SliderTarget, SliderZone, ZoneAction,
+};
+use crate::device::DeviceId;
+use crate::error::{DriverError, Result};
+use crate::numpad::{PalmRejection, PressureGate};
+use evdev::KeyCode;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Directories searched for `<model>.toml` layout files, in priority order
+pub const LAYOUT_DIRS: [&str; 2] = [
+    "/etc/asus-rog-touchpad/layouts",
+    "/usr/share/asus-rog-touchpad/layouts",
+];
+
+/// On-disk layout definition
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LayoutDefinition {
+    name: String,
+    description: Option<String>,
+    /// Extra names the layout can be selected by
+    #[serde(default)]
+    aliases: Vec<String>,
+    /// Touchpad `vendor:product` IDs the layout is made for
+    #[serde(default)]
+    touchpad_ids: Vec<String>,
+    #[serde(default)]
+    top_offset: f64,
+    #[serde(default)]
+    hitbox: HitboxTolerance,
+    #[serde(default)]
+    palm: PalmRejection,
+    pressure_threshold: Option<f64>,
+    #[serde(default = "default_try_times")]
+    try_times: u32,
+    #[serde(default = "default_try_sleep_ms")]
+    try_sleep_ms: u64,
+    i2c_address: Option<u8>,
+    toggle: Option<RectDefinition>,
+    calculator: Option<RectDefinition>,
+    layer_switch: Option<RectDefinition>,
+    #[serde(default)]
+    zones: Vec<ZoneDefinition>,
+    #[serde(default)]
+    sliders: Vec<SliderDefinition>,
+    grid: Option<GridDefinition>,
+    #[serde(default)]
+    keys: Vec<KeyDefinition>,
+    #[serde(default)]
+    layers: Vec<LayerDefinition>,
+}
+
+/// Extra key layer; `remap` reuses the base regions with substituted keys
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LayerDefinition {
+    name: String,
+    #[serde(default)]
+    remap: BTreeMap<String, String>,
+    grid: Option<GridDefinition>,
+    #[serde(default)]
+    keys: Vec<KeyDefinition>,
+}
+
+/// Rectangle given as `[start, end)` bands on each axis
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RectDefinition {
+    x: Band,
+    y: Band,
+}
+
+/// Control zone, either an `x`/`y` rectangle or a `polygon`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ZoneDefinition {
+    name: Option<String>,
+    action: ZoneActionDefinition,
+    x: Option<Band>,
+    y: Option<Band>,
+    polygon: Option<Vec<(f64, f64)>>,
+}
+
+/// `"toggle"`, `"calculator"`, `"brightness"`, `"layer_switch"` or a key action
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ZoneActionDefinition {
+    Builtin(String),
+    Action(ActionDefinition),
+}
+
+/// Slider strip, either an `x`/`y` rectangle or a `polygon`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SliderDefinition {
+    name: Option<String>,
+    action: SliderActionDefinition,
+    axis: SliderAxis,
+    x: Option<Band>,
+    y: Option<Band>,
+    polygon: Option<Vec<(f64, f64)>>,
+}
+
+/// `"brightness"` or keys clicked per `step` travelled
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum SliderActionDefinition {
+    Builtin(String),
+    Keys {
+        decrease: String,
+        increase: String,
+        step: f64,
+    },
+}
+
+/// Grid of key names; bands default to a uniform split below `top_offset`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GridDefinition {
+    columns: Option<Vec<Band>>,
+    rows: Option<Vec<Band>>,
+    keys: Vec<Vec<String>>,
+}
+
+/// Single key hitbox, either an `x`/`y` rectangle or a `polygon`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyDefinition {
+    key: String,
+    name: Option<String>,
+    x: Option<Band>,
+    y: Option<Band>,
+    polygon: Option<Vec<(f64, f64)>>,
+    /// Performed instead of pressing `key`
+    action: Option<ActionDefinition>,
+}
+
+fn default_try_times() -> u32 {
+    5
+}
+
+fn default_try_sleep_ms() -> u64 {
+    100
+}
+
+/// Layout loaded from a TOML definition
+#[derive(Debug)]
+pub struct FileLayout {
+    name: String,
+    description: Option<String>,
+    aliases: Vec<String>,
+    touchpad_ids: Vec<DeviceId>,
+    try_times: u32,
+    try_sleep_ms: u64,
+    i2c_address: Option<u8>,
+    hitbox: HitboxTolerance,
+    palm: PalmRejection,
+    pressure_threshold: Option<f64>,
+    zones: Vec<ControlZone>,
+    sliders: Vec<SliderZone>,
+    regions: Vec<KeyRegion>,
+    layers: Vec<KeyLayer>,
+}
+
+impl FileLayout {
+    /// Parse a layout from TOML source
+    pub fn from_toml(source: &str) -> Result<Self> {
+        let definition: LayoutDefinition =
+            toml::from_str(source).map_err(|e| DriverError::InvalidLayout(e.to_string()))?;
+        Self::from_definition(definition)
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+
+    pub fn touchpad_ids(&self) -> &[DeviceId] {
+        &self.touchpad_ids
+    }
+
+    /// Load a layout from a TOML file
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|e| {
+            DriverError::InvalidLayout(format!("Cannot read {}: {}", path.display(), e))
+        })?;
+        Self::from_toml(&source).map_err(|e| match e {
+            DriverError::InvalidLayout(reason) => {
+                DriverError::InvalidLayout(format!("{}: {}", path.display(), reason))
+            }
+            other => other,
+        })
+    }
+
+    fn from_definition(definition: LayoutDefinition) -> Result<Self> {
+        if !(0.0..1.0).contains(&definition.top_offset) {
+            return Err(DriverError::InvalidLayout(format!(
+                "top_offset must be in 0.0..1.0, got {}",
+                definition.top_offset
+            )));
+        }
+
+        definition
+            .hitbox
+            .check()
+            .map_err(DriverError::InvalidLayout)?;
+        if let Some(threshold) = definition.pressure_threshold {
+            PressureGate::check_threshold(threshold).map_err(DriverError::InvalidLayout)?;
+        }
+
+        let mut regions = Vec::new();
+        if let Some(grid) = definition.grid {
+            regions.extend(grid_key_regions(&grid, definition.top_offset)?);
+        }
+        for key in definition.keys {
+            regions.push(key_region(key)?);
+        }
+
+        let mut layers = Vec::with_capacity(definition.layers.len());
+        for layer in definition.layers {
+            layers.push(key_layer(layer, &regions, definition.top_offset)?);
+        }
+
+        let mut zones = Vec::new();
+        for (action, rect) in [
+            (ZoneAction::Toggle, definition.toggle),
+            (ZoneAction::Calculator, definition.calculator),
+            (ZoneAction::LayerSwitch, definition.layer_switch),
+        ] {
+            if let Some(rect) = rect {
+                check_band(rect.x)?;
+                check_band(rect.y)?;
+                zones.push(ControlZone::rect(action, rect.x, rect.y));
+            }
+        }
+        for zone in definition.zones {
+            zones.push(control_zone(zone)?);
+        }
+        // Default toggle and calculator zones fill in behind the layout's own
+        if !zones.iter().any(|zone| zone.action == ZoneAction::Toggle) {
+            zones.push(default_toggle_zone());
+        }
+        if !zones
+            .iter()
+            .any(|zone| zone.action == ZoneAction::Calculator)
+        {
+            zones.push(default_calculator_zone());
+        }
+
+        let sliders = definition
+            .sliders
+            .into_iter()
+            .map(slider_zone)
+            .collect::<Result<_>>()?;
+
+        let touchpad_ids = definition
+            .touchpad_ids
+            .iter()
+            .map(|id| {
+                id.parse::<DeviceId>().map_err(|e| match e {
+                    DriverError::ParseError(reason) => DriverError::InvalidLayout(reason),
+                    other => other,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            name: definition.name,
+            description: definition.description,
+            aliases: definition
+                .aliases
+                .iter()
+                .map(|alias| alias.to_lowercase())
+                .collect(),
+            touchpad_ids,
+            try_times: definition.try_times,
+            try_sleep_ms: definition.try_sleep_ms,
+            i2c_address: definition.i2c_address,
+            hitbox: definition.hitbox,
+            palm: definition.palm,
+            pressure_threshold: definition.pressure_threshold,
+            zones,
+            sliders,
+            regions,
+            layers,
+        })
+    }
+}
+
+fn control_zone(definition: ZoneDefinition) -> Result<ControlZone> {
+    let action = match &definition.action {
+        ZoneActionDefinition::Builtin(name) => ZoneAction::from_name(name).ok_or_else(|| {
+            DriverError::InvalidLayout(format!(
+                "unknown zone action {:?} (expected toggle, calculator, brightness, layer_switch or a key action)",
+                name
+            ))
+        })?,
+        ZoneActionDefinition::Action(action) => {
+            ZoneAction::Action(action.parse().map_err(|reason| {
+                let name = definition.name.as_deref().unwrap_or("action");
+                DriverError::InvalidLayout(format!("zone {}: {}", name, reason))
+            })?)
+        }
+    };
+    let name = definition.name.unwrap_or_else(|| action.name().to_string());
+    let region = parse_region(
+        &format!("zone {}", name),
+        definition.x,
+        definition.y,
+        definition.polygon,
+    )?;
+
+    Ok(ControlZone::new(name, region, action))
+}
+
+fn slider_zone(definition: SliderDefinition) -> Result<SliderZone> {
+    let (default_name, target) = match definition.action {
+        SliderActionDefinition::Builtin(name) if name == "brightness" => {
+            ("brightness", SliderTarget::Brightness)
+        }
+        SliderActionDefinition::Builtin(name) => {
+            return Err(DriverError::InvalidLayout(format!(
+                "unknown slider action {:?} (expected brightness or decrease/increase keys)",
+                name
+            )))
+        }
+        SliderActionDefinition::Keys {
+            decrease,
+            increase,
+            step,
+        } => {
+            if !(step > 0.0 && step <= 1.0) {
+                return Err(DriverError::InvalidLayout(format!(
+                    "slider step must be in (0.0, 1.0], got {}",
+                    step
+                )));
+            }
+            let target = SliderTarget::Keys {
+                decrease: parse_key_code(&decrease)?,
+                increase: parse_key_code(&increase)?,
+                step,
+            };
+            ("slider", target)
+        }
+    };
+    let name = definition.name.unwrap_or_else(|| default_name.to_string());
+    let region = parse_region(
+        &format!("slider {}", name),
+        definition.x,
+        definition.y,
+        definition.polygon,
+    )?;
+
+    Ok(SliderZone::new(name, region, definition.axis, target))
+}
+
+fn key_layer(definition: LayerDefinition, base: &[KeyRegion], top_offset: f64) -> Result<KeyLayer> {
+    let mut map = Vec::with_capacity(definition.remap.len());
+    for (from, to) in &definition.remap {
+        map.push((parse_key_code(from)?, parse_key_code(to)?));
+    }
+
+    let mut layer = KeyLayer::remapped(definition.name, base, &map);
+    if let Some(grid) = definition.grid {
+        layer.regions.extend(grid_key_regions(&grid, top_offset)?);
+    }
+    for key in definition.keys {
+        layer.regions.push(key_region(key)?);
+    }
+
+    Ok(layer)
+}
+
+fn key_region(definition: KeyDefinition) -> Result<KeyRegion> {
+    let key = parse_key_code(&definition.key)?;
+    let region = parse_region(
+        &format!("key {}", definition.key),
+        definition.x,
+        definition.y,
+        definition.polygon,
+    )?;
+    let name = definition.name.unwrap_or_else(|| key_name(key));
+    let mut hitbox = KeyRegion::new(name, key, region);
+    if let Some(action) = &definition.action {
+        let action = action.parse().map_err(|reason| {
+            DriverError::InvalidLayout(format!("key {}: {}", definition.key, reason))
+        })?;
+        hitbox = hitbox.with_action(action);
+    }
+
+    Ok(hitbox)
+}
+
+/// Hitbox of the key or zone described by `label`
+fn parse_region(
+    label: &str,
+    x: Option<Band>,
+    y: Option<Band>,
+    polygon: Option<Vec<(f64, f64)>>,
+) -> Result<Region> {
+    match (x, y, polygon) {
+        (Some(x), Some(y), None) => {
+            check_band(x)?;
+            check_band(y)?;
+            Ok(Region::Rect { x, y })
+        }
+        (None, None, Some(points)) => {
+            if points.len() < 3 {
+                return Err(DriverError::InvalidLayout(format!(
+                    "polygon for {} needs at least 3 points",
+                    label
+                )));
+            }
+            if points
+                .iter()
+                .any(|&(x, y)| !(0.0..=1.0).contains(&x) || !(0.0..=1.0).contains(&y))
+            {
+                return Err(DriverError::InvalidLayout(format!(
+                    "polygon for {} must stay within 0.0..=1.0",
+                    label
+                )));
+            }
+            Ok(Region::Polygon(points))
+        }
+        _ => Err(DriverError::InvalidLayout(format!(
+            "{} needs either both x and y bands or a polygon",
+            label
+        ))),
+    }
+}
+
+fn grid_key_regions(grid: &GridDefinition, top_offset: f64) -> Result<Vec<KeyRegion>> {
+    let row_count = grid.keys.len();
+    let col_count = grid.keys.iter().map(Vec::len).max().unwrap_or(0);
+    if row_count == 0 || col_count == 0 {
+        return Err(DriverError::InvalidLayout("grid has no keys".to_string()));
+    }
+
+    let columns = match &grid.columns {
+        Some(columns) => columns.clone(),
+        None => uniform_bands(0.0, 1.0, col_count),
+    };
+    let rows = match &grid.rows {
+        Some(rows) => rows.clone(),
+        None => uniform_bands(top_offset, 1.0, row_count),
+    };
+    if columns.len() != col_count || rows.len() != row_count {
+        return Err(DriverError::InvalidLayout(format!(
+            "grid is {}x{} but {} column and {} row bands were given",
+            row_count,
+            col_count,
+            columns.len(),
+            rows.len()
+        )));
+    }
+    for band in columns.iter().chain(&rows) {
+        check_band(*band)?;
+    }
+
+    // Empty cells leave a dead area in the grid
+    let mut keys = Vec::with_capacity(row_count);
+    for names in &grid.keys {
+        let mut row = vec![KeyCode::KEY_RESERVED; col_count];
+        for (cell, name) in row.iter_mut().zip(names) {
+            if !name.is_empty() {
+                *cell = parse_key_code(name)?;
+            }
+        }
+        keys.push(row);
+    }
+
+    Ok(grid_regions(&keys, &columns, &rows))
+}
+
+fn check_band((start, end): Band) -> Result<()> {
+    if (0.0..=1.0).contains(&start) && (0.0..=1.0).contains(&end) && start < end {
+        Ok(())
+    } else {
+        Err(DriverError::InvalidLayout(format!(
+            "band [{}, {}] must be increasing and within 0.0..=1.0",
+            start, end
+        )))
+    }
+}
+
+/// Parse an evdev key name such as `KEY_KP7`
+pub fn parse_key_code(name: &str) -> Result<KeyCode> {
+    KeyCode::from_str(name)
+        .map_err(|_| DriverError::InvalidLayout(format!("unknown key name: {}", name)))
+}
+
+impl NumpadLayout for FileLayout {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn regions(&self) -> &[KeyRegion] {
+        &self.regions
+    }
+
+    fn layers(&self) -> &[KeyLayer] {
+        &self.layers
+    }
+
+    fn zones(&self) -> &[ControlZone] {
+        &self.zones
+    }
+
+    fn sliders(&self) -> &[SliderZone] {
+        &self.sliders
+    }
+
+    fn hitbox_tolerance(&self) -> HitboxTolerance {
+        self.hitbox
+    }
+
+    fn palm_rejection(&self) -> PalmRejection {
+        self.palm
+    }
+
+    fn pressure_threshold(&self) -> Option<f64> {
+        self.pressure_threshold
+    }
+
+    fn try_times(&self) -> u32 {
+        self.try_times
+    }
+
+    fn try_sleep_ms(&self) -> u64 {
+        self.try_sleep_ms
+    }
+
+    fn i2c_address(&self) -> Option<u8> {
+        self.i2c_address
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::{zone_action_at, ZoneAction};
+    use crate::layouts::{Action, KeyStroke};
+
+    #[test]
+    fn uniform_grid_matches_default_grid_lookup() {
+        let layout = FileLayout::from_toml(
+            r#"
+name = "uniform"
+top_offset = 0.10
+
+[grid]
+keys = [
+    ["KEY_KP7", "KEY_KP8"],
+    ["", "KEY_KPENTER"],
+]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(layout.key_at_position(0.2, 0.05), None);
+        assert_eq!(layout.key_at_position(0.2, 0.20), Some(KeyCode::KEY_KP7));
+        assert_eq!(layout.key_at_position(0.7, 0.20), Some(KeyCode::KEY_KP8));
+        assert_eq!(layout.key_at_position(0.2, 0.80), None);
+        assert_eq!(layout.key_at_position(1.0, 1.0), Some(KeyCode::KEY_KPENTER));
+        assert_eq!(layout.all_keys().len(), 3);
+    }
+
+    #[test]
+    fn reads_control_zones_and_i2c_address() {
+        let layout = FileLayout::from_toml(
+            r#"
+name = "zones"
+i2c_address = 0x15
+
+[toggle]
+x = [0.90, 1.00]
+y = [0.00, 0.10]
+
+[calculator]
+x = [0.00, 0.10]
+y = [0.00, 0.10]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            zone_action_at(&layout, 0.95, 0.05),
+            Some(ZoneAction::Toggle)
+        );
+        assert_ne!(
+            zone_action_at(&layout, 0.85, 0.05),
+            Some(ZoneAction::Toggle)
+        );
+        assert_eq!(
+            zone_action_at(&layout, 0.08, 0.08),
+            Some(ZoneAction::Calculator)
+        );
+        assert_eq!(layout.i2c_address(), Some(0x15));
+    }
+
+    #[test]
+    fn reads_zone_list_with_actions() {
+        let layout = FileLayout::from_toml(
+            r#"
+name = "zones"
+
+[[zones]]
+action = "toggle"
+polygon = [[0.9, 0.0], [1.0, 0.0], [1.0, 0.1]]
+
+[[zones]]
+name = "screenshot"
+action = { combo = "Shift+Print" }
+x = [0.0, 0.1]
+y = [0.9, 1.0]
+
+[[zones]]
+action = "brightness"
+x = [0.0, 0.1]
+y = [0.0, 0.1]
+"#,
+        )
+        .unwrap();
+
+        let names: Vec<&str> = layout.zones().iter().map(|z| z.name.as_str()).collect();
+        assert_eq!(names, ["toggle", "screenshot", "brightness", "calculator"]);
+        assert_eq!(
+            zone_action_at(&layout, 0.99, 0.02),
+            Some(ZoneAction::Toggle)
+        );
+        assert_eq!(zone_action_at(&layout, 0.91, 0.09), None);
+        assert_eq!(
+            zone_action_at(&layout, 0.05, 0.05),
+            Some(ZoneAction::Brightness)
+        );
+        assert_eq!(
+            zone_action_at(&layout, 0.05, 0.95),
+            Some(ZoneAction::Action(Action::Combo(KeyStroke::new(
+                &[KeyCode::KEY_LEFTSHIFT],
+                KeyCode::KEY_PRINT
+            ))))
+        );
+        assert!(layout.all_keys().contains(&KeyCode::KEY_PRINT));
+
+        let err = FileLayout::from_toml(
+            r#"
+name = "broken"
+
+[[zones]]
+action = "volume"
+x = [0.0, 0.1]
+y = [0.0, 0.1]
+"#,
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string().contains("unknown zone action \"volume\""),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn reads_named_polygon_keys() {
+        let layout = FileLayout::from_toml(
+            r#"
+name = "polygon"
+
+[[keys]]
+key = "KEY_KPENTER"
+name = "enter"
+polygon = [[0.5, 0.5], [1.0, 0.5], [1.0, 1.0]]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(layout.regions()[0].name, "enter");
+        assert_eq!(layout.key_at_position(0.9, 0.6), Some(KeyCode::KEY_KPENTER));
+        assert_eq!(layout.key_at_position(0.6, 0.9), None);
+    }
+
+    #[test]
+    fn reads_remapped_and_explicit_layers() {
+        let layout = FileLayout::from_toml(
+            r#"
+name = "layers"
+
+[layer_switch]
+x = [0.0, 0.1]
+y = [0.0, 0.1]
+
+[grid]
+keys = [["KEY_KP8", "KEY_KP9"]]
+
+[[layers]]
+name = "navigation"
+remap = { KEY_KP8 = "KEY_UP", KEY_KP9 = "KEY_PAGEUP" }
+
+[[layers]]
+name = "function"
+
+[[layers.keys]]
+key = "KEY_F1"
+x = [0.0, 1.0]
+y = [0.0, 1.0]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(layout.layer_count(), 3);
+        assert_eq!(layout.layer_name(1), "navigation");
+        assert_eq!(
+            layout.key_at_layer_position(1, 0.2, 0.5),
+            Some(KeyCode::KEY_UP)
+        );
+        assert_eq!(
+            layout.key_at_layer_position(1, 0.7, 0.5),
+            Some(KeyCode::KEY_PAGEUP)
+        );
+        assert_eq!(
+            layout.key_at_layer_position(2, 0.7, 0.5),
+            Some(KeyCode::KEY_F1)
+        );
+        assert_eq!(
+            zone_action_at(&layout, 0.08, 0.08),
+            Some(ZoneAction::LayerSwitch)
+        );
+        // Zones from the file take precedence over the default calculator zone
+        assert_eq!(
+            zone_action_at(&layout, 0.03, 0.03),
+            Some(ZoneAction::LayerSwitch)
+        );
+        assert_ne!(
+            zone_action_at(&layout, 0.5, 0.05),
+            Some(ZoneAction::LayerSwitch)
+        );
+        assert!(layout.all_keys().contains(&KeyCode::KEY_F1));
+    }
+
+    #[test]
+    fn rejects_unknown_key_names() {
+        let err = FileLayout::from_toml(
+            r#"
+name = "broken"
+
+[[keys]]
+key = "KEY_KP77"
+x = [0.0, 0.5]
+y = [0.0, 0.5]
+"#,
+        )
+        .unwrap_err();
+
+        assert!(err.to_string().contains("KEY_KP77"), "{err}");
+    }
+
+    #[test]
+    fn parses_key_actions() {
+        let layout = FileLayout::from_toml(
+            r#"
+name = "actions"
+
+[[keys]]
+key = "KEY_5"
+name = "percent"
+x = [0.0, 0.5]
+y = [0.0, 1.0]
+action = { combo = "Shift+5" }
+
+[[keys]]
+key = "KEY_KP0"
+x = [0.5, 1.0]
+y = [0.0, 1.0]
+action = { text = "00" }
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            layout.action_at_layer_position(0, 0.2, 0.5),
+            Some(Action::Combo(KeyStroke::new(
+                &[KeyCode::KEY_LEFTSHIFT],
+                KeyCode::KEY_5
+            )))
+        );
+        assert_eq!(
+            layout.action_at_layer_position(0, 0.7, 0.5),
+            Some(Action::Text("00".to_string()))
+        );
+        assert!(layout.all_keys().contains(&KeyCode::KEY_0));
+
+        let err = FileLayout::from_toml(
+            r#"
+name = "broken"
+
+[[keys]]
+key = "KEY_KP1"
+x = [0.0, 0.5]
+y = [0.0, 0.5]
+action = { combo = "Hyper+1" }
+"#,
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("key KEY_KP1: unknown key name \"Hyper\""),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn reads_hitbox_tolerance() {
+        let layout = FileLayout::from_toml(
+            r#"
+name = "snapping"
+
+[hitbox]
+mode = "snap"
+max_distance = 0.05
+
+[grid]
+columns = [[0.0, 0.4], [0.6, 1.0]]
+keys = [["KEY_KP1", "KEY_KP2"]]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            layout.hitbox_tolerance(),
+            HitboxTolerance::Snap { max_distance: 0.05 }
+        );
+        assert_eq!(layout.key_at_position(0.44, 0.5), Some(KeyCode::KEY_KP1));
+        assert_eq!(layout.key_at_position(0.5, 0.5), None);
+
+        let err =
+            FileLayout::from_toml("name = \"broken\"\n[hitbox]\nmode = \"shrink\"\nmargin = 0.5")
+                .unwrap_err();
+        assert!(err.to_string().contains("hitbox margin"), "{err}");
+    }
+
+    #[test]
+    fn reads_palm_thresholds() {
+        let layout = FileLayout::from_toml(
+            r#"
+name = "palms"
+
+[palm]
+touch_major = 1200
+pressure = 120
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            layout.palm_rejection(),
+            PalmRejection {
+                touch_major: Some(1200),
+                touch_minor: None,
+                pressure: Some(120),
+                tool_type: true,
+            }
+        );
+        assert_eq!(
+            FileLayout::from_toml("name = \"plain\"")
+                .unwrap()
+                .palm_rejection(),
+            PalmRejection::default()
+        );
+    }
+
+    #[test]
+    fn reads_pressure_threshold() {
+        let layout = FileLayout::from_toml("name = \"firm\"\npressure_threshold = 0.4").unwrap();
+        assert_eq!(layout.pressure_threshold(), Some(0.4));
+
+        let err = FileLayout::from_toml("name = \"broken\"\npressure_threshold = 1.5").unwrap_err();
+        assert!(err.to_string().contains("pressure_threshold"), "{err}");
+    }
+
+    #[test]
+    fn reads_sliders_and_registers_their_keys() {
+        let layout = FileLayout::from_toml(
+            r#"
+name = "sliders"
+
+[[sliders]]
+action = { decrease = "KEY_SCROLLDOWN", increase = "KEY_SCROLLUP", step = 0.1 }
+axis = "vertical"
+x = [0.95, 1.0]
+y = [0.3, 1.0]
+"#,
+        )
+        .unwrap();
+
+        let slider = layout.slider_at_position(0.97, 0.5).unwrap();
+        assert_eq!(slider.name, "slider");
+        assert_eq!(slider.axis, SliderAxis::Vertical);
+        assert!(layout.all_keys().contains(&KeyCode::KEY_SCROLLUP));
+        // The default toggle zone wins over the slider below it
+        assert!(layout.slider_at_position(0.97, 0.1).is_none());
+
+        let err = FileLayout::from_toml(
+            "name = \"bad\"\n[[sliders]]\naction = \"volume\"\naxis = \"vertical\"\nx = [0.9, 1.0]\ny = [0.0, 1.0]",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("unknown slider action"), "{err}");
+    }
+
+    #[test]
+    fn rejects_mismatched_grid_bands() {
+        let result = FileLayout::from_toml(
+            r#"
+name = "broken"
+
+[grid]
+columns = [[0.0, 0.5]]
+keys = [["KEY_KP1", "KEY_KP2"]]
+"#,
+        );
+
+        assert!(result.is_err());
+    }
+}
@@ -1,55 +1,69 @@
-use super::NumpadLayout;
+use super::{
+    default_calculator_zone, grid_regions, Band, ControlZone, KeyRegion, NumpadLayout, ZoneAction,
+};
 use evdev::KeyCode;
 
-const NUMERIC_COLUMNS: [(f64, f64); 3] = [(0.05, 0.22), (0.25, 0.40), (0.45, 0.55)];
-const OPERATOR_COLUMN: (f64, f64) = (0.60, 0.75);
-const RIGHT_COLUMN: (f64, f64) = (0.80, 0.95);
-const ZERO_COLUMN: (f64, f64) = (0.05, 0.40);
-const DOT_COLUMN: (f64, f64) = (0.45, 0.60);
+const NUMERIC_COLUMNS: [Band; 3] = [(0.05, 0.22), (0.25, 0.40), (0.45, 0.55)];
+const OPERATOR_COLUMN: Band = (0.60, 0.75);
+const RIGHT_COLUMN: Band = (0.80, 0.95);
+const ZERO_COLUMN: Band = (0.05, 0.40);
+const DOT_COLUMN: Band = (0.45, 0.60);
 
-const MAIN_ROWS: [(f64, f64); 4] = [(0.05, 0.25), (0.30, 0.50), (0.55, 0.75), (0.80, 0.95)];
-const OPERATOR_ROWS: [(f64, f64); 4] = [(0.05, 0.30), (0.30, 0.55), (0.60, 0.75), (0.75, 0.95)];
-const RIGHT_ROWS: [(f64, f64); 3] = [(0.00, 0.30), (0.30, 0.50), (0.55, 0.95)];
+const MAIN_ROWS: [Band; 4] = [(0.05, 0.25), (0.30, 0.50), (0.55, 0.75), (0.80, 0.95)];
+const OPERATOR_ROWS: [Band; 4] = [(0.05, 0.30), (0.30, 0.55), (0.60, 0.75), (0.75, 0.95)];
+const RIGHT_ROWS: [Band; 3] = [(0.00, 0.30), (0.30, 0.50), (0.55, 0.95)];
 
 /// ROG Strix SCAR 16 G634JY / G634JYR layout
 /// ASUF1416:00 2808:0108
 /// LED backlight works using I2C address 0x38
 pub struct G634jyLayout {
-    keys: [[KeyCode; 5]; 4],
+    regions: Vec<KeyRegion>,
+    zones: Vec<ControlZone>,
 }
 
 impl G634jyLayout {
     pub fn new() -> Self {
+        let mut regions = grid_regions(
+            &[
+                [KeyCode::KEY_KP7, KeyCode::KEY_KP8, KeyCode::KEY_KP9],
+                [KeyCode::KEY_KP4, KeyCode::KEY_KP5, KeyCode::KEY_KP6],
+                [KeyCode::KEY_KP1, KeyCode::KEY_KP2, KeyCode::KEY_KP3],
+            ],
+            &NUMERIC_COLUMNS,
+            &MAIN_ROWS[..3],
+        );
+        regions.push(KeyRegion::rect(KeyCode::KEY_KP0, ZERO_COLUMN, MAIN_ROWS[3]));
+        regions.push(KeyRegion::rect(
+            KeyCode::KEY_KPDOT,
+            DOT_COLUMN,
+            MAIN_ROWS[3],
+        ));
+        regions.extend(grid_regions(
+            &[
+                [KeyCode::KEY_KPSLASH],
+                [KeyCode::KEY_KPASTERISK],
+                [KeyCode::KEY_KPMINUS],
+                [KeyCode::KEY_KPPLUS],
+            ],
+            &[OPERATOR_COLUMN],
+            &OPERATOR_ROWS,
+        ));
+        regions.push(KeyRegion::rect(
+            KeyCode::KEY_BACKSPACE,
+            RIGHT_COLUMN,
+            RIGHT_ROWS[1],
+        ));
+        regions.push(KeyRegion::rect(
+            KeyCode::KEY_KPENTER,
+            RIGHT_COLUMN,
+            RIGHT_ROWS[2],
+        ));
+
         Self {
-            keys: [
-                [
-                    KeyCode::KEY_KP7,
-                    KeyCode::KEY_KP8,
-                    KeyCode::KEY_KP9,
-                    KeyCode::KEY_KPSLASH,
-                    KeyCode::KEY_BACKSPACE,
-                ],
-                [
-                    KeyCode::KEY_KP4,
-                    KeyCode::KEY_KP5,
-                    KeyCode::KEY_KP6,
-                    KeyCode::KEY_KPASTERISK,
-                    KeyCode::KEY_BACKSPACE,
-                ],
-                [
-                    KeyCode::KEY_KP1,
-                    KeyCode::KEY_KP2,
-                    KeyCode::KEY_KP3,
-                    KeyCode::KEY_KPMINUS,
-                    KeyCode::KEY_KPENTER,
-                ],
-                [
-                    KeyCode::KEY_KP0,
-                    KeyCode::KEY_KP0,
-                    KeyCode::KEY_KPDOT,
-                    KeyCode::KEY_KPPLUS,
-                    KeyCode::KEY_KPENTER,
-                ],
+            regions,
+            zones: vec![
+                ControlZone::rect(ZoneAction::Toggle, RIGHT_COLUMN, RIGHT_ROWS[0]),
+                default_calculator_zone(),
             ],
         }
     }
@@ -61,170 +75,153 @@ impl Default for G634jyLayout {
     }
 }
 
-fn in_band(value: f64, (start, end): (f64, f64)) -> bool {
-    value >= start && (value < end || (end >= 1.0 && value <= end))
-}
-
-fn band_index(value: f64, bands: &[(f64, f64)]) -> Option<usize> {
-    bands.iter().position(|band| in_band(value, *band))
-}
-
 impl NumpadLayout for G634jyLayout {
-    fn name(&self) -> &'static str {
+    fn name(&self) -> &str {
         "g634jy"
     }
 
-    fn cols(&self) -> u32 {
-        5
-    }
-
-    fn rows(&self) -> u32 {
-        4
+    fn regions(&self) -> &[KeyRegion] {
+        &self.regions
     }
 
-    fn top_offset(&self) -> f64 {
-        0.10
-    }
-
-    fn key_at(&self, row: u32, col: u32) -> Option<KeyCode> {
-        self.keys
-            .get(row as usize)
-            .and_then(|r| r.get(col as usize))
-            .copied()
-    }
-
-    fn is_toggle_position(&self, x: f64, y: f64) -> bool {
-        in_band(x, RIGHT_COLUMN) && in_band(y, RIGHT_ROWS[0])
-    }
-
-    fn key_at_position(&self, x: f64, y: f64) -> Option<KeyCode> {
-        if in_band(x, RIGHT_COLUMN) {
-            return match band_index(y, &RIGHT_ROWS)? {
-                0 => None,
-                1 => Some(KeyCode::KEY_BACKSPACE),
-                2 => Some(KeyCode::KEY_KPENTER),
-                _ => None,
-            };
-        }
-
-        if in_band(x, OPERATOR_COLUMN) {
-            let row = band_index(y, &OPERATOR_ROWS)?;
-            return Some(match row {
-                0 => KeyCode::KEY_KPSLASH,
-                1 => KeyCode::KEY_KPASTERISK,
-                2 => KeyCode::KEY_KPMINUS,
-                3 => KeyCode::KEY_KPPLUS,
-                _ => return None,
-            });
-        }
-
-        if in_band(y, MAIN_ROWS[3]) {
-            if in_band(x, ZERO_COLUMN) {
-                return Some(KeyCode::KEY_KP0);
-            }
-            if in_band(x, DOT_COLUMN) {
-                return Some(KeyCode::KEY_KPDOT);
-            }
-        }
-
-        let row = band_index(y, &MAIN_ROWS)?;
-        let col = band_index(x, &NUMERIC_COLUMNS)?;
-        Some(match row {
-            0 => [KeyCode::KEY_KP7, KeyCode::KEY_KP8, KeyCode::KEY_KP9][col],
-            1 => [KeyCode::KEY_KP4, KeyCode::KEY_KP5, KeyCode::KEY_KP6][col],
-            2 => [KeyCode::KEY_KP1, KeyCode::KEY_KP2, KeyCode::KEY_KP3][col],
-            _ => return None,
-        })
-    }
-
-    fn all_keys(&self) -> Vec<KeyCode> {
-        vec![
-            KeyCode::KEY_KP0,
-            KeyCode::KEY_KP1,
-            KeyCode::KEY_KP2,
-            KeyCode::KEY_KP3,
-            KeyCode::KEY_KP4,
-            KeyCode::KEY_KP5,
-            KeyCode::KEY_KP6,
-            KeyCode::KEY_KP7,
-            KeyCode::KEY_KP8,
-            KeyCode::KEY_KP9,
-            KeyCode::KEY_KPDOT,
-            KeyCode::KEY_KPENTER,
-            KeyCode::KEY_KPPLUS,
-            KeyCode::KEY_KPMINUS,
-            KeyCode::KEY_KPASTERISK,
-            KeyCode::KEY_KPSLASH,
-            KeyCode::KEY_BACKSPACE,
-        ]
+    fn zones(&self) -> &[ControlZone] {
+        &self.zones
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::layouts::FileLayout;
+    use crate::layouts::{zone_action_at, ZoneAction};
+
+    /// The built-in layout and its TOML definition must agree on every hitbox.
+    fn layouts() -> Vec<Box<dyn NumpadLayout>> {
+        vec![
+            Box::new(G634jyLayout::new()),
+            Box::new(
+                FileLayout::from_toml(include_str!("../../layouts/g634jy.toml"))
+                    .expect("bundled g634jy.toml should parse"),
+            ),
+        ]
+    }
 
-    fn key_at(x: f64, y: f64) -> Option<KeyCode> {
-        G634jyLayout::new().key_at_position(x, y)
+    fn assert_keys(cases: &[(f64, f64, Option<KeyCode>)]) {
+        for layout in layouts() {
+            for &(x, y, expected) in cases {
+                assert_eq!(layout.key_at_position(x, y), expected, "x={x}, y={y}");
+            }
+        }
     }
 
     #[test]
     fn maps_g634jy_photo_hitboxes() {
-        assert_eq!(key_at(0.14, 0.15), Some(KeyCode::KEY_KP7));
-        assert_eq!(key_at(0.32, 0.15), Some(KeyCode::KEY_KP8));
-        assert_eq!(key_at(0.50, 0.15), Some(KeyCode::KEY_KP9));
-        assert_eq!(key_at(0.14, 0.40), Some(KeyCode::KEY_KP4));
-        assert_eq!(key_at(0.32, 0.40), Some(KeyCode::KEY_KP5));
-        assert_eq!(key_at(0.50, 0.40), Some(KeyCode::KEY_KP6));
-        assert_eq!(key_at(0.14, 0.65), Some(KeyCode::KEY_KP1));
-        assert_eq!(key_at(0.32, 0.65), Some(KeyCode::KEY_KP2));
-        assert_eq!(key_at(0.50, 0.65), Some(KeyCode::KEY_KP3));
-        assert_eq!(key_at(0.14, 0.87), Some(KeyCode::KEY_KP0));
-        assert_eq!(key_at(0.32, 0.87), Some(KeyCode::KEY_KP0));
+        assert_keys(&[
+            (0.14, 0.15, Some(KeyCode::KEY_KP7)),
+            (0.32, 0.15, Some(KeyCode::KEY_KP8)),
+            (0.50, 0.15, Some(KeyCode::KEY_KP9)),
+            (0.14, 0.40, Some(KeyCode::KEY_KP4)),
+            (0.32, 0.40, Some(KeyCode::KEY_KP5)),
+            (0.50, 0.40, Some(KeyCode::KEY_KP6)),
+            (0.14, 0.65, Some(KeyCode::KEY_KP1)),
+            (0.32, 0.65, Some(KeyCode::KEY_KP2)),
+            (0.50, 0.65, Some(KeyCode::KEY_KP3)),
+            (0.14, 0.87, Some(KeyCode::KEY_KP0)),
+            (0.32, 0.87, Some(KeyCode::KEY_KP0)),
+        ]);
     }
 
     #[test]
     fn maps_g634jy_operator_and_control_strip_hitboxes() {
-        assert_eq!(key_at(0.67, 0.17), Some(KeyCode::KEY_KPSLASH));
-        assert_eq!(key_at(0.67, 0.42), Some(KeyCode::KEY_KPASTERISK));
-        assert_eq!(key_at(0.67, 0.67), Some(KeyCode::KEY_KPMINUS));
-        assert_eq!(key_at(0.67, 0.85), Some(KeyCode::KEY_KPPLUS));
-        assert_eq!(key_at(0.87, 0.15), None);
-        assert_eq!(key_at(0.87, 0.40), Some(KeyCode::KEY_BACKSPACE));
-        assert_eq!(key_at(0.87, 0.75), Some(KeyCode::KEY_KPENTER));
+        assert_keys(&[
+            (0.67, 0.17, Some(KeyCode::KEY_KPSLASH)),
+            (0.67, 0.42, Some(KeyCode::KEY_KPASTERISK)),
+            (0.67, 0.67, Some(KeyCode::KEY_KPMINUS)),
+            (0.67, 0.85, Some(KeyCode::KEY_KPPLUS)),
+            (0.87, 0.15, None),
+            (0.87, 0.40, Some(KeyCode::KEY_BACKSPACE)),
+            (0.87, 0.75, Some(KeyCode::KEY_KPENTER)),
+        ]);
     }
 
     #[test]
     fn detects_g634jy_toggle_zone_separately_from_keys() {
-        let layout = G634jyLayout::new();
+        for layout in layouts() {
+            assert_eq!(
+                zone_action_at(layout.as_ref(), 0.87, 0.15),
+                Some(ZoneAction::Toggle)
+            );
+            assert_ne!(
+                zone_action_at(layout.as_ref(), 0.87, 0.40),
+                Some(ZoneAction::Toggle)
+            );
+            assert_eq!(layout.key_at_position(0.87, 0.15), None);
+        }
+    }
 
-        assert!(layout.is_toggle_position(0.87, 0.15));
-        assert!(!layout.is_toggle_position(0.87, 0.40));
-        assert_eq!(layout.key_at_position(0.87, 0.15), None);
+    #[test]
+    fn ships_g634jy_without_sliders() {
+        // Edge sliders are opt-in through a copy of the layout file
+        for layout in layouts() {
+            assert!(layout.sliders().is_empty());
+            assert!(layout.slider_at_position(0.40, 0.02).is_none());
+            assert!(layout.slider_at_position(0.97, 0.60).is_none());
+        }
     }
 
     #[test]
     fn maps_g634jy_wide_zero_and_dot() {
-        assert_eq!(key_at(0.23, 0.87), Some(KeyCode::KEY_KP0));
-        assert_eq!(key_at(0.50, 0.87), Some(KeyCode::KEY_KPDOT));
-        assert_eq!(key_at(0.58, 0.87), Some(KeyCode::KEY_KPDOT));
+        assert_keys(&[
+            (0.23, 0.87, Some(KeyCode::KEY_KP0)),
+            (0.50, 0.87, Some(KeyCode::KEY_KPDOT)),
+            (0.58, 0.87, Some(KeyCode::KEY_KPDOT)),
+        ]);
     }
 
     #[test]
     fn leaves_g634jy_unlit_margins_dead() {
-        assert_eq!(key_at(0.02, 0.15), None);
-        assert_eq!(key_at(0.14, 0.98), None);
+        assert_keys(&[(0.02, 0.15, None), (0.14, 0.98, None)]);
     }
 
     #[test]
     fn leaves_g634jy_separator_gaps_dead() {
-        assert_eq!(key_at(0.23, 0.15), None);
-        assert_eq!(key_at(0.42, 0.15), None);
-        assert_eq!(key_at(0.57, 0.15), None);
-        assert_eq!(key_at(0.77, 0.15), None);
-        assert_eq!(key_at(0.14, 0.27), None);
-        assert_eq!(key_at(0.14, 0.52), None);
-        assert_eq!(key_at(0.14, 0.77), None);
-        assert_eq!(key_at(0.87, 0.52), None);
+        assert_keys(&[
+            (0.23, 0.15, None),
+            (0.42, 0.15, None),
+            (0.57, 0.15, None),
+            (0.77, 0.15, None),
+            (0.14, 0.27, None),
+            (0.14, 0.52, None),
+            (0.14, 0.77, None),
+            (0.87, 0.52, None),
+        ]);
+    }
+
+    #[test]
+    fn g634jy_zero_and_enter_are_single_regions() {
+        let layout = G634jyLayout::new();
+        let count = |key| layout.regions().iter().filter(|r| r.key == key).count();
+
+        assert_eq!(count(KeyCode::KEY_KP0), 1);
+        assert_eq!(count(KeyCode::KEY_KPENTER), 1);
+    }
+
+    #[test]
+    fn g634jy_file_registers_same_keys() {
+        let layouts = layouts();
+        let mut expected = layouts[0].all_keys();
+        let mut actual = layouts[1].all_keys();
+        expected.sort_by_key(|key| key.0);
+        actual.sort_by_key(|key| key.0);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn g634jy_leaves_i2c_address_to_detection() {
+        // Auto mode falls back to this layout on unknown hardware
+        for layout in layouts() {
+            assert_eq!(layout.i2c_address(), None);
+        }
     }
 }
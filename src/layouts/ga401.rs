@@ -0,0 +1,124 @@
+use super::{numberpad_zones, uniform_grid_regions, ControlZone, KeyRegion, NumpadLayout};
+use evdev::KeyCode;
+
+/// ROG Zephyrus G14 GA401 / G15 GA502 layout
+/// LED backlight works using I2C address 0x15
+/// The top tenth of the pad holds the printed icons and is not part of the grid
+pub struct Ga401Layout {
+    regions: Vec<KeyRegion>,
+    zones: Vec<ControlZone>,
+}
+
+impl Ga401Layout {
+    pub fn new() -> Self {
+        Self {
+            regions: uniform_grid_regions(
+                &[
+                    [
+                        KeyCode::KEY_KP7,
+                        KeyCode::KEY_KP8,
+                        KeyCode::KEY_KP9,
+                        KeyCode::KEY_KPSLASH,
+                        KeyCode::KEY_BACKSPACE,
+                    ],
+                    [
+                        KeyCode::KEY_KP4,
+                        KeyCode::KEY_KP5,
+                        KeyCode::KEY_KP6,
+                        KeyCode::KEY_KPASTERISK,
+                        KeyCode::KEY_BACKSPACE,
+                    ],
+                    [
+                        KeyCode::KEY_KP1,
+                        KeyCode::KEY_KP2,
+                        KeyCode::KEY_KP3,
+                        KeyCode::KEY_KPMINUS,
+                        KeyCode::KEY_KPENTER,
+                    ],
+                    [
+                        KeyCode::KEY_KP0,
+                        KeyCode::KEY_KP0,
+                        KeyCode::KEY_KPDOT,
+                        KeyCode::KEY_KPPLUS,
+                        KeyCode::KEY_KPENTER,
+                    ],
+                ],
+                0.10,
+            ),
+            zones: numberpad_zones(),
+        }
+    }
+}
+
+impl Default for Ga401Layout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NumpadLayout for Ga401Layout {
+    fn name(&self) -> &str {
+        "ga401"
+    }
+
+    fn regions(&self) -> &[KeyRegion] {
+        &self.regions
+    }
+
+    fn zones(&self) -> &[ControlZone] {
+        &self.zones
+    }
+
+    fn i2c_address(&self) -> Option<u8> {
+        Some(0x15)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::{zone_action_at, ZoneAction};
+
+    fn key_at(x: f64, y: f64) -> Option<KeyCode> {
+        Ga401Layout::new().key_at_position(x, y)
+    }
+
+    #[test]
+    fn skips_ga401_icon_strip() {
+        assert_eq!(key_at(0.10, 0.05), None);
+        assert_eq!(key_at(0.10, 0.15), Some(KeyCode::KEY_KP7));
+    }
+
+    #[test]
+    fn maps_ga401_grid_below_top_offset() {
+        // Rows are 0.225 tall starting at y = 0.10
+        assert_eq!(key_at(0.30, 0.20), Some(KeyCode::KEY_KP8));
+        assert_eq!(key_at(0.50, 0.40), Some(KeyCode::KEY_KP6));
+        assert_eq!(key_at(0.70, 0.40), Some(KeyCode::KEY_KPASTERISK));
+        assert_eq!(key_at(0.10, 0.60), Some(KeyCode::KEY_KP1));
+        assert_eq!(key_at(0.90, 0.60), Some(KeyCode::KEY_KPENTER));
+        assert_eq!(key_at(0.30, 0.90), Some(KeyCode::KEY_KP0));
+        assert_eq!(key_at(0.50, 0.90), Some(KeyCode::KEY_KPDOT));
+        assert_eq!(key_at(0.70, 0.90), Some(KeyCode::KEY_KPPLUS));
+    }
+
+    #[test]
+    fn detects_ga401_icon_corners() {
+        let layout = Ga401Layout::new();
+
+        assert_eq!(
+            zone_action_at(&layout, 0.97, 0.05),
+            Some(ZoneAction::Toggle)
+        );
+        assert_ne!(
+            zone_action_at(&layout, 0.97, 0.15),
+            Some(ZoneAction::Toggle)
+        );
+        assert_eq!(
+            zone_action_at(&layout, 0.03, 0.03),
+            Some(ZoneAction::Calculator)
+        );
+        assert_eq!(layout.key_at_position(0.97, 0.05), None);
+        assert_eq!(layout.i2c_address(), Some(0x15));
+    }
+}
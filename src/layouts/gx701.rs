@@ -0,0 +1,112 @@
+use super::{numberpad_zones, uniform_grid_regions, ControlZone, KeyRegion, NumpadLayout};
+use evdev::KeyCode;
+
+/// ROG Zephyrus S GX701 layout
+/// LED backlight works using I2C address 0x15
+/// Desktop-style grid with a tall `+` and Enter in the right column
+pub struct Gx701Layout {
+    regions: Vec<KeyRegion>,
+    zones: Vec<ControlZone>,
+}
+
+impl Gx701Layout {
+    pub fn new() -> Self {
+        Self {
+            regions: uniform_grid_regions(
+                &[
+                    [
+                        KeyCode::KEY_BACKSPACE,
+                        KeyCode::KEY_KPSLASH,
+                        KeyCode::KEY_KPASTERISK,
+                        KeyCode::KEY_KPMINUS,
+                    ],
+                    [
+                        KeyCode::KEY_KP7,
+                        KeyCode::KEY_KP8,
+                        KeyCode::KEY_KP9,
+                        KeyCode::KEY_KPPLUS,
+                    ],
+                    [
+                        KeyCode::KEY_KP4,
+                        KeyCode::KEY_KP5,
+                        KeyCode::KEY_KP6,
+                        KeyCode::KEY_KPPLUS,
+                    ],
+                    [
+                        KeyCode::KEY_KP1,
+                        KeyCode::KEY_KP2,
+                        KeyCode::KEY_KP3,
+                        KeyCode::KEY_KPENTER,
+                    ],
+                    [
+                        KeyCode::KEY_KP0,
+                        KeyCode::KEY_KP0,
+                        KeyCode::KEY_KPDOT,
+                        KeyCode::KEY_KPENTER,
+                    ],
+                ],
+                0.0,
+            ),
+            zones: numberpad_zones(),
+        }
+    }
+}
+
+impl Default for Gx701Layout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NumpadLayout for Gx701Layout {
+    fn name(&self) -> &str {
+        "gx701"
+    }
+
+    fn regions(&self) -> &[KeyRegion] {
+        &self.regions
+    }
+
+    fn zones(&self) -> &[ControlZone] {
+        &self.zones
+    }
+
+    fn i2c_address(&self) -> Option<u8> {
+        Some(0x15)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_at(x: f64, y: f64) -> Option<KeyCode> {
+        Gx701Layout::new().key_at_position(x, y)
+    }
+
+    #[test]
+    fn maps_gx701_operator_row() {
+        assert_eq!(key_at(0.10, 0.10), Some(KeyCode::KEY_BACKSPACE));
+        assert_eq!(key_at(0.40, 0.10), Some(KeyCode::KEY_KPSLASH));
+        assert_eq!(key_at(0.60, 0.10), Some(KeyCode::KEY_KPASTERISK));
+        assert_eq!(key_at(0.85, 0.15), Some(KeyCode::KEY_KPMINUS));
+    }
+
+    #[test]
+    fn maps_gx701_tall_plus_and_enter() {
+        assert_eq!(key_at(0.90, 0.30), Some(KeyCode::KEY_KPPLUS));
+        assert_eq!(key_at(0.90, 0.50), Some(KeyCode::KEY_KPPLUS));
+        assert_eq!(key_at(0.90, 0.70), Some(KeyCode::KEY_KPENTER));
+        assert_eq!(key_at(0.90, 0.90), Some(KeyCode::KEY_KPENTER));
+    }
+
+    #[test]
+    fn maps_gx701_digits_and_wide_zero() {
+        assert_eq!(key_at(0.10, 0.30), Some(KeyCode::KEY_KP7));
+        assert_eq!(key_at(0.40, 0.50), Some(KeyCode::KEY_KP5));
+        assert_eq!(key_at(0.60, 0.70), Some(KeyCode::KEY_KP3));
+        assert_eq!(key_at(0.10, 0.90), Some(KeyCode::KEY_KP0));
+        assert_eq!(key_at(0.40, 0.90), Some(KeyCode::KEY_KP0));
+        assert_eq!(key_at(0.60, 0.90), Some(KeyCode::KEY_KPDOT));
+    }
+}
@@ -0,0 +1,214 @@
+use super::{KeyRegion, NumpadLayout};
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// How touches near the edge of a key or in the gaps between keys resolve
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case", deny_unknown_fields)]
+pub enum HitboxTolerance {
+    /// Hitboxes are used as drawn; gaps between keys are dead
+    #[default]
+    Exact,
+    /// Every hitbox loses `margin` on each side, widening the dead gaps
+    Shrink { margin: f64 },
+    /// Touches in a gap go to the nearest key, within the area the keys span
+    Expand,
+    /// Touches outside every key go to the nearest one up to `max_distance` away
+    Snap { max_distance: f64 },
+}
+
+impl HitboxTolerance {
+    /// Check the margin or distance is usable
+    pub fn check(&self) -> Result<(), String> {
+        match *self {
+            HitboxTolerance::Shrink { margin } if !(0.0..0.5).contains(&margin) => {
+                Err(format!("hitbox margin must be in 0.0..0.5, got {}", margin))
+            }
+            HitboxTolerance::Snap { max_distance } if !(0.0..=1.0).contains(&max_distance) => {
+                Err(format!(
+                    "hitbox max_distance must be in 0.0..=1.0, got {}",
+                    max_distance
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Region a touch at the normalized position resolves to; the first match wins
+    pub fn region_at<'a>(&self, regions: &'a [KeyRegion], x: f64, y: f64) -> Option<&'a KeyRegion> {
+        let exact = || regions.iter().find(|region| region.contains(x, y));
+        match *self {
+            HitboxTolerance::Exact => exact(),
+            HitboxTolerance::Shrink { margin } => regions
+                .iter()
+                .find(|region| region.region.contains_inset(x, y, margin)),
+            HitboxTolerance::Expand => exact().or_else(|| {
+                let ((x0, x1), (y0, y1)) = key_bounds(regions)?;
+                if (x0..=x1).contains(&x) && (y0..=y1).contains(&y) {
+                    nearest_region(regions, x, y, f64::INFINITY)
+                } else {
+                    None
+                }
+            }),
+            HitboxTolerance::Snap { max_distance } => {
+                exact().or_else(|| nearest_region(regions, x, y, max_distance))
+            }
+        }
+    }
+}
+
+/// Bounding box of all regions as x and y bands
+fn key_bounds(regions: &[KeyRegion]) -> Option<((f64, f64), (f64, f64))> {
+    regions
+        .iter()
+        .map(|region| region.region.bounds())
+        .reduce(|(ax, ay), (bx, by)| {
+            (
+                (ax.0.min(bx.0), ax.1.max(bx.1)),
+                (ay.0.min(by.0), ay.1.max(by.1)),
+            )
+        })
+}
+
+/// Closest region no further than `max_distance`; earlier regions win ties
+fn nearest_region(regions: &[KeyRegion], x: f64, y: f64, max_distance: f64) -> Option<&KeyRegion> {
+    regions
+        .iter()
+        .map(|region| (region.region.distance(x, y), region))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .fold(
+            None,
+            |best: Option<(f64, &KeyRegion)>, candidate| match best {
+                Some(best) if best.0 <= candidate.0 => Some(best),
+                _ => Some(candidate),
+            },
+        )
+        .map(|(_, region)| region)
+}
+
+/// Layout wrapper that resolves touches with a different hitbox tolerance
+pub struct HitboxLayout {
+    inner: Arc<dyn NumpadLayout>,
+    tolerance: HitboxTolerance,
+}
+
+impl HitboxLayout {
+    pub fn new(inner: Arc<dyn NumpadLayout>, tolerance: HitboxTolerance) -> Self {
+        Self { inner, tolerance }
+    }
+}
+
+impl NumpadLayout for HitboxLayout {
+    fn inner(&self) -> Option<&dyn NumpadLayout> {
+        Some(self.inner.as_ref())
+    }
+
+    fn hitbox_tolerance(&self) -> HitboxTolerance {
+        self.tolerance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::G634jyLayout;
+    use evdev::KeyCode;
+
+    fn g634jy(tolerance: HitboxTolerance) -> HitboxLayout {
+        HitboxLayout::new(Arc::new(G634jyLayout::new()), tolerance)
+    }
+
+    fn assert_keys(layout: &HitboxLayout, cases: &[(f64, f64, Option<KeyCode>)]) {
+        for &(x, y, expected) in cases {
+            assert_eq!(layout.key_at_position(x, y), expected, "x={x}, y={y}");
+        }
+    }
+
+    #[test]
+    fn shrink_widens_g634jy_gaps() {
+        let layout = g634jy(HitboxTolerance::Shrink { margin: 0.01 });
+
+        assert_keys(
+            &layout,
+            &[
+                (0.14, 0.15, Some(KeyCode::KEY_KP7)),
+                (0.23, 0.87, Some(KeyCode::KEY_KP0)),
+                (0.055, 0.15, None),
+                (0.215, 0.15, None),
+                (0.14, 0.055, None),
+                (0.87, 0.495, None),
+                (0.23, 0.15, None),
+            ],
+        );
+    }
+
+    #[test]
+    fn expand_fills_g634jy_separator_gaps_but_not_margins() {
+        let layout = g634jy(HitboxTolerance::Expand);
+
+        assert_keys(
+            &layout,
+            &[
+                (0.23, 0.15, Some(KeyCode::KEY_KP7)),
+                (0.42, 0.15, Some(KeyCode::KEY_KP8)),
+                (0.57, 0.15, Some(KeyCode::KEY_KP9)),
+                (0.77, 0.15, Some(KeyCode::KEY_KPSLASH)),
+                (0.14, 0.27, Some(KeyCode::KEY_KP7)),
+                (0.14, 0.52, Some(KeyCode::KEY_KP4)),
+                (0.14, 0.77, Some(KeyCode::KEY_KP1)),
+                (0.87, 0.52, Some(KeyCode::KEY_BACKSPACE)),
+                (0.02, 0.15, None),
+                (0.14, 0.98, None),
+            ],
+        );
+    }
+
+    #[test]
+    fn snap_reaches_g634jy_keys_within_max_distance() {
+        let layout = g634jy(HitboxTolerance::Snap {
+            max_distance: 0.015,
+        });
+
+        assert_keys(
+            &layout,
+            &[
+                (0.14, 0.15, Some(KeyCode::KEY_KP7)),
+                (0.23, 0.15, Some(KeyCode::KEY_KP7)),
+                (0.42, 0.15, None),
+                (0.02, 0.15, None),
+                (0.87, 0.52, None),
+            ],
+        );
+
+        let layout = g634jy(HitboxTolerance::Snap {
+            max_distance: 0.035,
+        });
+        assert_keys(
+            &layout,
+            &[
+                (0.42, 0.15, Some(KeyCode::KEY_KP8)),
+                (0.02, 0.15, Some(KeyCode::KEY_KP7)),
+                (0.14, 0.98, Some(KeyCode::KEY_KP0)),
+                (0.87, 0.52, Some(KeyCode::KEY_BACKSPACE)),
+            ],
+        );
+    }
+
+    #[test]
+    fn parses_and_checks_modes() {
+        let parse = |source: &str| toml::from_str::<HitboxTolerance>(source);
+
+        assert_eq!(parse("mode = \"exact\"").unwrap(), HitboxTolerance::Exact);
+        assert_eq!(parse("mode = \"expand\"").unwrap(), HitboxTolerance::Expand);
+        assert_eq!(
+            parse("mode = \"snap\"\nmax_distance = 0.03").unwrap(),
+            HitboxTolerance::Snap { max_distance: 0.03 }
+        );
+        assert!(parse("mode = \"shrink\"").is_err());
+        assert!(parse("mode = \"stretch\"").is_err());
+        assert!(HitboxTolerance::Shrink { margin: 0.6 }.check().is_err());
+        assert!(HitboxTolerance::Snap { max_distance: -0.1 }
+            .check()
+            .is_err());
+    }
+}
@@ -0,0 +1,123 @@
+use super::{
+    numberpad_zones, percent_key, uniform_grid_regions, ControlZone, KeyRegion, NumpadLayout,
+};
+use evdev::KeyCode;
+
+/// VivoBook 14 M433IA / Zenbook 14 UP5401EA layout
+/// LED backlight works using I2C address 0x15
+/// The printed `%` key types Shift+5
+pub struct M433iaLayout {
+    regions: Vec<KeyRegion>,
+    zones: Vec<ControlZone>,
+}
+
+impl M433iaLayout {
+    pub fn new() -> Self {
+        let mut regions = uniform_grid_regions(
+            &[
+                [
+                    KeyCode::KEY_KP7,
+                    KeyCode::KEY_KP8,
+                    KeyCode::KEY_KP9,
+                    KeyCode::KEY_KPSLASH,
+                    KeyCode::KEY_BACKSPACE,
+                ],
+                [
+                    KeyCode::KEY_KP4,
+                    KeyCode::KEY_KP5,
+                    KeyCode::KEY_KP6,
+                    KeyCode::KEY_KPASTERISK,
+                    KeyCode::KEY_BACKSPACE,
+                ],
+                [
+                    KeyCode::KEY_KP1,
+                    KeyCode::KEY_KP2,
+                    KeyCode::KEY_KP3,
+                    KeyCode::KEY_KPMINUS,
+                    KeyCode::KEY_5,
+                ],
+                [
+                    KeyCode::KEY_KP0,
+                    KeyCode::KEY_KPDOT,
+                    KeyCode::KEY_KPENTER,
+                    KeyCode::KEY_KPPLUS,
+                    KeyCode::KEY_KPEQUAL,
+                ],
+            ],
+            0.0,
+        );
+        percent_key(&mut regions);
+
+        Self {
+            regions,
+            zones: numberpad_zones(),
+        }
+    }
+}
+
+impl Default for M433iaLayout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NumpadLayout for M433iaLayout {
+    fn name(&self) -> &str {
+        "m433ia"
+    }
+
+    fn regions(&self) -> &[KeyRegion] {
+        &self.regions
+    }
+
+    fn zones(&self) -> &[ControlZone] {
+        &self.zones
+    }
+
+    fn i2c_address(&self) -> Option<u8> {
+        Some(0x15)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::{Action, KeyStroke};
+
+    fn key_at(x: f64, y: f64) -> Option<KeyCode> {
+        M433iaLayout::new().key_at_position(x, y)
+    }
+
+    #[test]
+    fn maps_m433ia_digits_and_operators() {
+        assert_eq!(key_at(0.10, 0.10), Some(KeyCode::KEY_KP7));
+        assert_eq!(key_at(0.50, 0.40), Some(KeyCode::KEY_KP6));
+        assert_eq!(key_at(0.70, 0.10), Some(KeyCode::KEY_KPSLASH));
+        assert_eq!(key_at(0.70, 0.40), Some(KeyCode::KEY_KPASTERISK));
+        assert_eq!(key_at(0.70, 0.60), Some(KeyCode::KEY_KPMINUS));
+        assert_eq!(key_at(0.90, 0.30), Some(KeyCode::KEY_BACKSPACE));
+    }
+
+    #[test]
+    fn maps_m433ia_bottom_row() {
+        assert_eq!(key_at(0.10, 0.90), Some(KeyCode::KEY_KP0));
+        assert_eq!(key_at(0.30, 0.90), Some(KeyCode::KEY_KPDOT));
+        assert_eq!(key_at(0.50, 0.90), Some(KeyCode::KEY_KPENTER));
+        assert_eq!(key_at(0.70, 0.90), Some(KeyCode::KEY_KPPLUS));
+        assert_eq!(key_at(0.90, 0.90), Some(KeyCode::KEY_KPEQUAL));
+    }
+
+    #[test]
+    fn m433ia_percent_key_types_shift_5() {
+        let layout = M433iaLayout::new();
+
+        assert_eq!(
+            layout.action_at_layer_position(0, 0.90, 0.60),
+            Some(Action::Combo(KeyStroke::new(
+                &[KeyCode::KEY_LEFTSHIFT],
+                KeyCode::KEY_5
+            )))
+        );
+        assert!(layout.all_keys().contains(&KeyCode::KEY_LEFTSHIFT));
+    }
+}
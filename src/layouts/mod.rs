@@ -1,132 +1,361 @@
+mod action;
+mod calibration;
+mod file;
 mod g634jy;
+mod ga401;
+mod gx701;
+mod hitbox;
+mod m433ia;
+mod region;
+mod registry;
+mod remap;
+mod render;
+mod slider;
+mod transform;
+mod ux433fa;
+mod ux581;
+mod validate;
+mod zone;
 
-use crate::error::{DriverError, Result};
+use crate::error::Result;
+use crate::numpad::PalmRejection;
 use evdev::KeyCode;
 use std::sync::Arc;
 
+pub use action::{Action, ActionDefinition, KeyStroke};
+pub use calibration::{calibrated_hitboxes, calibrated_layout_toml, CalibrationTarget};
+pub use file::{parse_key_code, FileLayout, LAYOUT_DIRS};
 pub use g634jy::G634jyLayout;
+pub use ga401::Ga401Layout;
+pub use gx701::Gx701Layout;
+pub use hitbox::{HitboxLayout, HitboxTolerance};
+pub use m433ia::M433iaLayout;
+pub use region::{
+    grid_regions, key_name, uniform_bands, uniform_grid_regions, Band, KeyLayer, KeyRegion, Region,
+};
+pub use registry::{LayoutRegistry, LayoutSource};
+pub(crate) use remap::region_keys;
+pub use remap::{KeyRemap, RemapSource, RemappedLayout};
+pub use render::{render_ascii, render_svg, ASCII_COLUMNS, ASCII_ROWS};
+pub use slider::{SliderAxis, SliderTarget, SliderZone};
+pub use transform::{LayoutTransform, TransformedLayout};
+pub use ux433fa::Ux433faLayout;
+pub use ux581::Ux581Layout;
+pub use validate::{validate_layout, DEFAULT_VALIDATION_STEP};
+pub use zone::{default_calculator_zone, default_toggle_zone, ControlZone, ZoneAction};
+
+/// Name of layer 0, the layout's own numpad regions
+pub const BASE_LAYER_NAME: &str = "numpad";
 
 /// Trait defining a numpad layout
+///
+/// Wrappers that adjust another layout return it from [`NumpadLayout::inner`];
+/// everything they do not override then comes from the wrapped layout.
 #[allow(dead_code)]
 pub trait NumpadLayout: Send + Sync {
+    /// Layout this one wraps, if any
+    fn inner(&self) -> Option<&dyn NumpadLayout> {
+        None
+    }
+
     /// Layout name for identification
-    fn name(&self) -> &'static str;
+    fn name(&self) -> &str {
+        self.inner().map_or("", |inner| inner.name())
+    }
 
-    /// Number of columns in the grid
-    fn cols(&self) -> u32;
+    /// Key hitboxes in normalized coordinates; the first region containing a point wins
+    fn regions(&self) -> &[KeyRegion] {
+        match self.inner() {
+            Some(inner) => inner.regions(),
+            None => &[],
+        }
+    }
 
-    /// Number of rows in the grid
-    fn rows(&self) -> u32;
+    /// Extra key layers cycled in over the numpad; the base regions are layer 0
+    fn layers(&self) -> &[KeyLayer] {
+        match self.inner() {
+            Some(inner) => inner.layers(),
+            None => &[],
+        }
+    }
 
-    /// Vertical offset as fraction (0.0 - 1.0) to skip top control area
-    fn top_offset(&self) -> f64;
+    /// Number of layers including the base numpad layer
+    fn layer_count(&self) -> usize {
+        1 + self.layers().len()
+    }
 
-    /// Get the key at the given grid position
-    /// Returns None if position is invalid
-    fn key_at(&self, row: u32, col: u32) -> Option<KeyCode>;
+    /// Name of the given layer
+    fn layer_name(&self, layer: usize) -> &str {
+        match layer {
+            0 => BASE_LAYER_NAME,
+            n => self
+                .layers()
+                .get(n - 1)
+                .map_or(BASE_LAYER_NAME, |l| l.name.as_str()),
+        }
+    }
 
-    /// Returns true when the normalized position is inside the numpad toggle zone.
-    fn is_toggle_position(&self, x: f64, y: f64) -> bool {
-        x > 0.80 && y < 0.25
+    /// Regions of the given layer, falling back to the base regions
+    fn layer_regions(&self, layer: usize) -> &[KeyRegion] {
+        match layer {
+            0 => self.regions(),
+            n => self
+                .layers()
+                .get(n - 1)
+                .map_or(self.regions(), |l| l.regions.as_slice()),
+        }
     }
 
-    /// Get the key at the given normalized touchpad position.
-    fn key_at_position(&self, x: f64, y: f64) -> Option<KeyCode> {
-        let cols = self.cols() as f64;
-        let rows = self.rows() as f64;
-        if cols == 0.0 || rows == 0.0 {
-            return None;
+    /// Control zones checked before the keys; the first zone containing a point wins
+    fn zones(&self) -> &[ControlZone] {
+        match self.inner() {
+            Some(inner) => inner.zones(),
+            None => &[],
+        }
+    }
+
+    /// Get the control zone at the given normalized touchpad position.
+    fn zone_at_position(&self, x: f64, y: f64) -> Option<&ControlZone> {
+        self.zones().iter().find(|zone| zone.contains(x, y))
+    }
+
+    /// Strips along the pad edges that turn drags into continuous controls
+    fn sliders(&self) -> &[SliderZone] {
+        match self.inner() {
+            Some(inner) => inner.sliders(),
+            None => &[],
         }
+    }
 
-        let top_offset = self.top_offset().clamp(0.0, 0.95);
-        if y < top_offset {
+    /// Get the slider at the given normalized position; control zones take precedence
+    fn slider_at_position(&self, x: f64, y: f64) -> Option<&SliderZone> {
+        if self.zone_at_position(x, y).is_some() {
             return None;
         }
+        self.sliders().iter().find(|slider| slider.contains(x, y))
+    }
 
-        let col = scaled_index(x, cols);
-        let row = scaled_index((y - top_offset) / (1.0 - top_offset), rows);
-        if col >= 0 && col < self.cols() as i32 && row >= 0 && row < self.rows() as i32 {
-            self.key_at(row as u32, col as u32)
-        } else {
-            None
-        }
+    /// Position of a touch at the given normalized position along `slider`
+    fn slider_fraction(&self, slider: &SliderZone, x: f64, y: f64) -> f64 {
+        slider.fraction(x, y)
+    }
+
+    /// Contact size and pressure limits for ignoring palms and resting thumbs
+    fn palm_rejection(&self) -> PalmRejection {
+        self.inner()
+            .map_or_else(PalmRejection::default, |inner| inner.palm_rejection())
+    }
+
+    /// Pressure, normalized over the touchpad's pressure range, a finger must
+    /// reach before its key registers
+    fn pressure_threshold(&self) -> Option<f64> {
+        self.inner().and_then(|inner| inner.pressure_threshold())
+    }
+
+    /// I2C address of the LED backlight, or None to use the detected address
+    fn i2c_address(&self) -> Option<u8> {
+        self.inner().and_then(|inner| inner.i2c_address())
+    }
+
+    /// How touches near key edges and in the gaps between keys resolve
+    fn hitbox_tolerance(&self) -> HitboxTolerance {
+        self.inner()
+            .map_or(HitboxTolerance::Exact, |inner| inner.hitbox_tolerance())
+    }
+
+    /// Region hit at the given normalized position on a specific layer.
+    fn region_at_layer_position(&self, layer: usize, x: f64, y: f64) -> Option<&KeyRegion> {
+        self.hitbox_tolerance()
+            .region_at(self.layer_regions(layer), x, y)
+    }
+
+    /// Get the key at the given normalized touchpad position.
+    fn key_at_position(&self, x: f64, y: f64) -> Option<KeyCode> {
+        self.key_at_layer_position(0, x, y)
+    }
+
+    /// Get the key at the given normalized position on a specific layer.
+    fn key_at_layer_position(&self, layer: usize, x: f64, y: f64) -> Option<KeyCode> {
+        self.region_at_layer_position(layer, x, y)
+            .map(|region| region.key)
+    }
+
+    /// Action at the given normalized position on a specific layer.
+    fn action_at_layer_position(&self, layer: usize, x: f64, y: f64) -> Option<Action> {
+        self.region_at_layer_position(layer, x, y)
+            .map(KeyRegion::action)
     }
 
-    /// All keys used by this layout (for enabling in virtual device)
-    fn all_keys(&self) -> Vec<KeyCode>;
+    /// All keys used by any layer, control zone or slider of this layout, including
+    /// the modifiers and keys its actions type (for enabling in virtual device)
+    fn all_keys(&self) -> Vec<KeyCode> {
+        let mut keys: Vec<KeyCode> = Vec::new();
+        let mut add = |key: KeyCode| {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        };
+        for layer in 0..self.layer_count() {
+            for region in self.layer_regions(layer) {
+                add(region.key);
+                region
+                    .action
+                    .iter()
+                    .flat_map(Action::keys)
+                    .for_each(&mut add);
+            }
+        }
+        for zone in self.zones() {
+            zone.action.keys().into_iter().for_each(&mut add);
+        }
+        for slider in self.sliders() {
+            slider.target.keys().into_iter().for_each(&mut add);
+        }
+        keys
+    }
 
     /// Number of detection retry attempts
     fn try_times(&self) -> u32 {
-        5
+        self.inner().map_or(5, |inner| inner.try_times())
     }
 
     /// Sleep duration between retry attempts in milliseconds
     fn try_sleep_ms(&self) -> u64 {
-        100
+        self.inner().map_or(100, |inner| inner.try_sleep_ms())
+    }
+}
+
+/// Toggle and calculator zones used by the upstream Python driver for NumberPad models.
+pub(crate) fn numberpad_zones() -> Vec<ControlZone> {
+    vec![
+        ControlZone::rect(ZoneAction::Toggle, (0.95, 1.0), (0.0, 0.09)),
+        default_calculator_zone(),
+    ]
+}
+
+/// Put a Shift+5 action on the printed `%` key, laid out as a `KEY_5` cell
+pub(crate) fn percent_key(regions: &mut [KeyRegion]) {
+    for region in regions.iter_mut().filter(|r| r.key == KeyCode::KEY_5) {
+        region.name = "percent".to_string();
+        region.action = Some(Action::Combo(KeyStroke::new(
+            &[KeyCode::KEY_LEFTSHIFT],
+            KeyCode::KEY_5,
+        )));
     }
 }
 
-fn scaled_index(value: f64, count: f64) -> i32 {
-    (count * value.clamp(0.0, 1.0)).floor().min(count - 1.0) as i32
+/// Returns true when `value` lies in the half-open band, closing it at the pad edge.
+pub(crate) fn in_band(value: f64, (start, end): (f64, f64)) -> bool {
+    value >= start && (value < end || (end >= 1.0 && value <= end))
 }
 
-/// Get a layout by name
+/// Get a layout by name, alias or TOML file path
+///
+/// Layout files in [`LAYOUT_DIRS`] take precedence over the built-in layouts.
 pub fn get_layout(name: &str) -> Result<Arc<dyn NumpadLayout>> {
-    match name.to_lowercase().as_str() {
-        "g634jy" | "g634jyr" => Ok(Arc::new(G634jyLayout::new())),
-        _ => Err(DriverError::LayoutNotFound(name.to_string())),
+    if name.ends_with(".toml") {
+        return Ok(Arc::new(FileLayout::from_path(name)?));
     }
+
+    LayoutRegistry::installed().get(name)
+}
+
+/// Action of the control zone at the given position
+#[cfg(test)]
+pub(crate) fn zone_action_at(layout: &dyn NumpadLayout, x: f64, y: f64) -> Option<ZoneAction> {
+    layout
+        .zone_at_position(x, y)
+        .map(|zone| zone.action.clone())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    struct TestLayout;
+    struct TestLayout {
+        regions: Vec<KeyRegion>,
+        layers: Vec<KeyLayer>,
+    }
 
     impl NumpadLayout for TestLayout {
-        fn name(&self) -> &'static str {
+        fn name(&self) -> &str {
             "test"
         }
 
-        fn cols(&self) -> u32 {
-            5
+        fn regions(&self) -> &[KeyRegion] {
+            &self.regions
         }
 
-        fn rows(&self) -> u32 {
-            4
+        fn layers(&self) -> &[KeyLayer] {
+            &self.layers
         }
 
-        fn top_offset(&self) -> f64 {
-            0.10
+        fn zones(&self) -> &[ControlZone] {
+            &[]
         }
+    }
 
-        fn key_at(&self, row: u32, col: u32) -> Option<KeyCode> {
-            match (row, col) {
-                (0, 2) => Some(KeyCode::KEY_KP2),
-                (3, 4) => Some(KeyCode::KEY_BACKSPACE),
-                _ => None,
-            }
-        }
+    #[test]
+    fn default_lookup_uses_first_matching_region_and_derives_keys() {
+        let layout = TestLayout {
+            regions: vec![
+                KeyRegion::rect(KeyCode::KEY_KP1, (0.0, 0.5), (0.0, 0.5)),
+                KeyRegion::new(
+                    "diamond",
+                    KeyCode::KEY_KP2,
+                    Region::Polygon(vec![(0.5, 0.3), (0.7, 0.5), (0.5, 0.7), (0.3, 0.5)]),
+                ),
+                KeyRegion::rect(KeyCode::KEY_KP1, (0.8, 1.0), (0.8, 1.0)),
+            ],
+            layers: Vec::new(),
+        };
 
-        fn all_keys(&self) -> Vec<KeyCode> {
-            Vec::new()
-        }
+        assert_eq!(layout.key_at_position(0.25, 0.25), Some(KeyCode::KEY_KP1));
+        assert_eq!(layout.key_at_position(0.45, 0.45), Some(KeyCode::KEY_KP1));
+        assert_eq!(layout.key_at_position(0.55, 0.55), Some(KeyCode::KEY_KP2));
+        assert_eq!(layout.key_at_position(0.35, 0.65), None);
+        assert_eq!(layout.key_at_position(1.0, 1.0), Some(KeyCode::KEY_KP1));
+        assert_eq!(layout.all_keys(), vec![KeyCode::KEY_KP1, KeyCode::KEY_KP2]);
     }
 
     #[test]
-    fn default_position_lookup_skips_top_offset_and_scales_grid() {
-        let layout = TestLayout;
+    fn layers_switch_lookup_and_register_union_of_keys() {
+        let regions = vec![
+            KeyRegion::rect(KeyCode::KEY_KP8, (0.0, 0.5), (0.0, 1.0)),
+            KeyRegion::rect(KeyCode::KEY_KP2, (0.5, 1.0), (0.0, 1.0)),
+        ];
+        let layout = TestLayout {
+            layers: vec![KeyLayer::remapped(
+                "navigation",
+                &regions,
+                &[
+                    (KeyCode::KEY_KP8, KeyCode::KEY_UP),
+                    (KeyCode::KEY_KP2, KeyCode::KEY_DOWN),
+                ],
+            )],
+            regions,
+        };
 
-        assert_eq!(layout.key_at_position(0.5, 0.09), None);
-        assert_eq!(layout.key_at_position(0.5, 0.10), Some(KeyCode::KEY_KP2));
+        assert_eq!(layout.layer_count(), 2);
+        assert_eq!(layout.layer_name(0), BASE_LAYER_NAME);
+        assert_eq!(layout.layer_name(1), "navigation");
+        assert_eq!(layout.key_at_position(0.2, 0.5), Some(KeyCode::KEY_KP8));
+        assert_eq!(
+            layout.key_at_layer_position(1, 0.2, 0.5),
+            Some(KeyCode::KEY_UP)
+        );
         assert_eq!(
-            layout.key_at_position(0.99, 0.99),
-            Some(KeyCode::KEY_BACKSPACE)
+            layout.key_at_layer_position(1, 0.7, 0.5),
+            Some(KeyCode::KEY_DOWN)
         );
         assert_eq!(
-            layout.key_at_position(1.0, 1.0),
-            Some(KeyCode::KEY_BACKSPACE)
+            layout.all_keys(),
+            vec![
+                KeyCode::KEY_KP8,
+                KeyCode::KEY_KP2,
+                KeyCode::KEY_UP,
+                KeyCode::KEY_DOWN
+            ]
         );
     }
 }
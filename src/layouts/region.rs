@@ -0,0 +1,396 @@
+use super::{in_band, Action};
+use evdev::KeyCode;
+
+/// Half-open `[start, end)` range along one axis in normalized coordinates
+pub type Band = (f64, f64);
+
+/// Shape of a hitbox in normalized touchpad coordinates (origin top-left)
+#[derive(Debug, Clone, PartialEq)]
+pub enum Region {
+    /// Axis-aligned rectangle; each band is closed at the pad edge
+    Rect { x: Band, y: Band },
+    /// Simple polygon given by its vertices in order
+    Polygon(Vec<(f64, f64)>),
+}
+
+impl Region {
+    /// Returns true when the normalized point lies inside the region
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        match self {
+            Region::Rect {
+                x: x_band,
+                y: y_band,
+            } => in_band(x, *x_band) && in_band(y, *y_band),
+            Region::Polygon(points) => polygon_contains(points, x, y),
+        }
+    }
+
+    /// Returns true when the point lies inside the region and at least
+    /// `margin` away from its edge
+    pub fn contains_inset(&self, x: f64, y: f64, margin: f64) -> bool {
+        match self {
+            Region::Rect {
+                x: x_band,
+                y: y_band,
+            } => {
+                in_band(x, (x_band.0 + margin, x_band.1 - margin))
+                    && in_band(y, (y_band.0 + margin, y_band.1 - margin))
+            }
+            Region::Polygon(points) => {
+                polygon_contains(points, x, y) && polygon_edge_distance(points, x, y) >= margin
+            }
+        }
+    }
+
+    /// Distance from the point to the region, 0.0 inside it
+    pub fn distance(&self, x: f64, y: f64) -> f64 {
+        if self.contains(x, y) {
+            return 0.0;
+        }
+        match self {
+            Region::Rect {
+                x: (x0, x1),
+                y: (y0, y1),
+            } => {
+                let dx = (x0 - x).max(x - x1).max(0.0);
+                let dy = (y0 - y).max(y - y1).max(0.0);
+                dx.hypot(dy)
+            }
+            Region::Polygon(points) => polygon_edge_distance(points, x, y),
+        }
+    }
+
+    /// Bounding box as x and y bands
+    pub fn bounds(&self) -> (Band, Band) {
+        match self {
+            Region::Rect { x, y } => (*x, *y),
+            Region::Polygon(points) => {
+                let fold = |axis: fn(&(f64, f64)) -> f64| {
+                    points
+                        .iter()
+                        .map(axis)
+                        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+                            (lo.min(v), hi.max(v))
+                        })
+                };
+                (fold(|p| p.0), fold(|p| p.1))
+            }
+        }
+    }
+}
+
+/// Even-odd ray casting test
+fn polygon_contains(points: &[(f64, f64)], x: f64, y: f64) -> bool {
+    if points.len() < 3 {
+        return false;
+    }
+
+    let mut inside = false;
+    let mut j = points.len() - 1;
+    for i in 0..points.len() {
+        let (xi, yi) = points[i];
+        let (xj, yj) = points[j];
+        if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+/// Distance from the point to the nearest polygon edge
+fn polygon_edge_distance(points: &[(f64, f64)], x: f64, y: f64) -> f64 {
+    let mut distance = f64::INFINITY;
+    let mut j = points.len().saturating_sub(1);
+    for i in 0..points.len() {
+        let (ax, ay) = points[j];
+        let (bx, by) = points[i];
+        let (dx, dy) = (bx - ax, by - ay);
+        let length = dx * dx + dy * dy;
+        let t = if length > 0.0 {
+            (((x - ax) * dx + (y - ay) * dy) / length).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        distance = distance.min((ax + t * dx - x).hypot(ay + t * dy - y));
+        j = i;
+    }
+    distance
+}
+
+/// Named key hitbox
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyRegion {
+    pub name: String,
+    pub key: KeyCode,
+    pub region: Region,
+    /// Replaces pressing `key` when set
+    pub action: Option<Action>,
+}
+
+impl KeyRegion {
+    pub fn new(name: impl Into<String>, key: KeyCode, region: Region) -> Self {
+        Self {
+            name: name.into(),
+            key,
+            region,
+            action: None,
+        }
+    }
+
+    pub fn with_action(mut self, action: Action) -> Self {
+        self.action = Some(action);
+        self
+    }
+
+    /// What touching this region does
+    pub fn action(&self) -> Action {
+        self.action.clone().unwrap_or(Action::Key(self.key))
+    }
+
+    /// Rectangular region named after its key
+    pub fn rect(key: KeyCode, x: Band, y: Band) -> Self {
+        Self::new(key_name(key), key, Region::Rect { x, y })
+    }
+
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        self.region.contains(x, y)
+    }
+}
+
+/// Named set of key regions that can be cycled in over the numpad surface
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyLayer {
+    pub name: String,
+    pub regions: Vec<KeyRegion>,
+}
+
+impl KeyLayer {
+    pub fn new(name: impl Into<String>, regions: Vec<KeyRegion>) -> Self {
+        Self {
+            name: name.into(),
+            regions,
+        }
+    }
+
+    /// Reuse the geometry of `base`, substituting keys through `map`
+    ///
+    /// Regions whose key is not in `map` are left out of the layer.
+    pub fn remapped(
+        name: impl Into<String>,
+        base: &[KeyRegion],
+        map: &[(KeyCode, KeyCode)],
+    ) -> Self {
+        let regions = base
+            .iter()
+            .filter_map(|region| {
+                map.iter()
+                    .find(|(from, _)| *from == region.key)
+                    .map(|&(_, to)| KeyRegion::new(region.name.clone(), to, region.region.clone()))
+            })
+            .collect();
+        Self::new(name, regions)
+    }
+}
+
+/// Short region name for a key, e.g. `kp7` for `KEY_KP7`
+pub fn key_name(key: KeyCode) -> String {
+    let name = format!("{:?}", key);
+    name.trim_start_matches("KEY_").to_lowercase()
+}
+
+/// Split `[start, end]` into `count` equal bands
+pub fn uniform_bands(start: f64, end: f64, count: usize) -> Vec<Band> {
+    let step = (end - start) / count as f64;
+    (0..count)
+        .map(|i| {
+            let band_end = if i + 1 == count {
+                end
+            } else {
+                start + step * (i + 1) as f64
+            };
+            (start + step * i as f64, band_end)
+        })
+        .collect()
+}
+
+/// Build key regions from a grid of keys and its column/row bands
+///
+/// Adjacent cells holding the same key are merged into one rectangle, so a
+/// double-width 0 or double-height Enter becomes a single region. Cells set
+/// to `KEY_RESERVED` are left dead.
+pub fn grid_regions<R: AsRef<[KeyCode]>>(
+    keys: &[R],
+    columns: &[Band],
+    rows: &[Band],
+) -> Vec<KeyRegion> {
+    let cell = |row: usize, col: usize| {
+        keys.get(row)
+            .and_then(|r| r.as_ref().get(col))
+            .copied()
+            .filter(|key| *key != KeyCode::KEY_RESERVED)
+    };
+
+    let mut taken = vec![vec![false; columns.len()]; rows.len()];
+    let mut regions = Vec::new();
+    for row in 0..rows.len() {
+        for col in 0..columns.len() {
+            let Some(key) = cell(row, col) else {
+                continue;
+            };
+            if taken[row][col] {
+                continue;
+            }
+
+            let mut last_col = col;
+            while last_col + 1 < columns.len()
+                && !taken[row][last_col + 1]
+                && cell(row, last_col + 1) == Some(key)
+            {
+                last_col += 1;
+            }
+
+            let mut last_row = row;
+            while last_row + 1 < rows.len()
+                && (col..=last_col)
+                    .all(|c| !taken[last_row + 1][c] && cell(last_row + 1, c) == Some(key))
+            {
+                last_row += 1;
+            }
+
+            for taken_row in taken.iter_mut().take(last_row + 1).skip(row) {
+                for taken_cell in taken_row.iter_mut().take(last_col + 1).skip(col) {
+                    *taken_cell = true;
+                }
+            }
+
+            regions.push(KeyRegion::rect(
+                key,
+                (columns[col].0, columns[last_col].1),
+                (rows[row].0, rows[last_row].1),
+            ));
+        }
+    }
+
+    regions
+}
+
+/// Build regions for a uniform grid covering the pad below `top_offset`
+pub fn uniform_grid_regions<R: AsRef<[KeyCode]>>(keys: &[R], top_offset: f64) -> Vec<KeyRegion> {
+    let cols = keys.iter().map(|r| r.as_ref().len()).max().unwrap_or(0);
+    grid_regions(
+        keys,
+        &uniform_bands(0.0, 1.0, cols),
+        &uniform_bands(top_offset, 1.0, keys.len()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polygon_contains_points_inside_only() {
+        let triangle = Region::Polygon(vec![(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]);
+
+        assert!(triangle.contains(0.2, 0.2));
+        assert!(!triangle.contains(0.8, 0.8));
+        assert_eq!(triangle.bounds(), ((0.0, 1.0), (0.0, 1.0)));
+    }
+
+    #[test]
+    fn measures_distance_and_inset_for_rects_and_polygons() {
+        let rect = Region::Rect {
+            x: (0.2, 0.4),
+            y: (0.2, 0.4),
+        };
+        let triangle = Region::Polygon(vec![(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]);
+
+        assert_eq!(rect.distance(0.3, 0.3), 0.0);
+        assert!((rect.distance(0.1, 0.3) - 0.1).abs() < 1e-9);
+        assert!((rect.distance(0.7, 0.8) - 0.5).abs() < 1e-9);
+        assert!((triangle.distance(1.0, 1.0) - 0.5_f64.sqrt()).abs() < 1e-9);
+        assert!(rect.contains_inset(0.3, 0.3, 0.05));
+        assert!(!rect.contains_inset(0.22, 0.3, 0.05));
+        assert!(triangle.contains_inset(0.2, 0.2, 0.1));
+        assert!(!triangle.contains_inset(0.45, 0.45, 0.1));
+    }
+
+    #[test]
+    fn remapped_layer_keeps_geometry_and_drops_unmapped_keys() {
+        let base = vec![
+            KeyRegion::rect(KeyCode::KEY_KP8, (0.0, 0.5), (0.0, 1.0)),
+            KeyRegion::rect(KeyCode::KEY_KP9, (0.5, 1.0), (0.0, 1.0)),
+        ];
+        let layer = KeyLayer::remapped("navigation", &base, &[(KeyCode::KEY_KP8, KeyCode::KEY_UP)]);
+
+        assert_eq!(layer.regions.len(), 1);
+        assert_eq!(layer.regions[0].name, "kp8");
+        assert_eq!(layer.regions[0].key, KeyCode::KEY_UP);
+        assert_eq!(layer.regions[0].region, base[0].region);
+    }
+
+    #[test]
+    fn uniform_grid_skips_top_offset_and_closes_pad_edge() {
+        let regions = uniform_grid_regions(
+            &[
+                [
+                    KeyCode::KEY_RESERVED,
+                    KeyCode::KEY_RESERVED,
+                    KeyCode::KEY_KP2,
+                    KeyCode::KEY_RESERVED,
+                    KeyCode::KEY_RESERVED,
+                ],
+                [KeyCode::KEY_RESERVED; 5],
+                [KeyCode::KEY_RESERVED; 5],
+                [
+                    KeyCode::KEY_RESERVED,
+                    KeyCode::KEY_RESERVED,
+                    KeyCode::KEY_RESERVED,
+                    KeyCode::KEY_RESERVED,
+                    KeyCode::KEY_BACKSPACE,
+                ],
+            ],
+            0.10,
+        );
+        let key_at = |x, y| regions.iter().find(|r| r.contains(x, y)).map(|r| r.key);
+
+        assert_eq!(key_at(0.5, 0.09), None);
+        assert_eq!(key_at(0.5, 0.10), Some(KeyCode::KEY_KP2));
+        assert_eq!(key_at(0.99, 0.99), Some(KeyCode::KEY_BACKSPACE));
+        assert_eq!(key_at(1.0, 1.0), Some(KeyCode::KEY_BACKSPACE));
+    }
+
+    #[test]
+    fn grid_merges_wide_and_tall_keys_into_single_regions() {
+        let regions = uniform_grid_regions(
+            &[
+                [KeyCode::KEY_KP1, KeyCode::KEY_KP2, KeyCode::KEY_KPENTER],
+                [KeyCode::KEY_KP0, KeyCode::KEY_KP0, KeyCode::KEY_KPENTER],
+            ],
+            0.0,
+        );
+
+        assert_eq!(regions.len(), 4);
+        let zero = regions.iter().find(|r| r.key == KeyCode::KEY_KP0).unwrap();
+        assert_eq!(zero.name, "kp0");
+        assert_eq!(
+            zero.region,
+            Region::Rect {
+                x: (0.0, 2.0 / 3.0),
+                y: (0.5, 1.0)
+            }
+        );
+        let enter = regions
+            .iter()
+            .find(|r| r.key == KeyCode::KEY_KPENTER)
+            .unwrap();
+        assert_eq!(
+            enter.region,
+            Region::Rect {
+                x: (2.0 / 3.0, 1.0),
+                y: (0.0, 1.0)
+            }
+        );
+    }
+}
@@ -0,0 +1,354 @@
+use super::{
+    FileLayout, G634jyLayout, Ga401Layout, Gx701Layout, M433iaLayout, NumpadLayout, Ux433faLayout,
+    Ux581Layout, LAYOUT_DIRS,
+};
+use crate::device::{DeviceId, HARDWARE_TABLE};
+use crate::error::{DriverError, Result};
+use log::warn;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+/// Built-in layout constructor with its registry metadata
+struct BuiltinLayout {
+    name: &'static str,
+    aliases: &'static [&'static str],
+    description: &'static str,
+    create: fn() -> Arc<dyn NumpadLayout>,
+}
+
+const BUILTIN_LAYOUTS: &[BuiltinLayout] = &[
+    BuiltinLayout {
+        name: "g634jy",
+        aliases: &["g634jyr"],
+        description: "ROG Strix SCAR 16 G634JY / G634JYR",
+        create: || Arc::new(G634jyLayout::new()),
+    },
+    BuiltinLayout {
+        name: "ga401",
+        aliases: &["ga502"],
+        description: "ROG Zephyrus G14 GA401 / G15 GA502",
+        create: || Arc::new(Ga401Layout::new()),
+    },
+    BuiltinLayout {
+        name: "gx701",
+        aliases: &[],
+        description: "ROG Zephyrus S GX701",
+        create: || Arc::new(Gx701Layout::new()),
+    },
+    BuiltinLayout {
+        name: "m433ia",
+        aliases: &["up5401ea"],
+        description: "VivoBook 14 M433IA / Zenbook 14 UP5401EA",
+        create: || Arc::new(M433iaLayout::new()),
+    },
+    BuiltinLayout {
+        name: "ux433fa",
+        aliases: &["ux425ea"],
+        description: "ZenBook 14 UX433FA / UX425EA",
+        create: || Arc::new(Ux433faLayout::new()),
+    },
+    BuiltinLayout {
+        name: "ux581",
+        aliases: &["ux581l"],
+        description: "ZenBook Pro Duo UX581",
+        create: || Arc::new(Ux581Layout::new()),
+    },
+];
+
+/// Where a registered layout is loaded from
+#[derive(Clone)]
+pub enum LayoutSource {
+    Builtin(fn() -> Arc<dyn NumpadLayout>),
+    /// Layout file, already parsed; the error is kept so loading reports it
+    File {
+        path: PathBuf,
+        layout: std::result::Result<Arc<FileLayout>, String>,
+    },
+}
+
+/// Layout known by name, with the aliases and touchpads it answers to
+#[derive(Clone)]
+pub struct LayoutEntry {
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub description: String,
+    pub touchpad_ids: Vec<DeviceId>,
+    pub source: LayoutSource,
+}
+
+impl LayoutEntry {
+    /// Build the layout; file entries hand out the layout parsed at registration
+    pub fn load(&self) -> Result<Arc<dyn NumpadLayout>> {
+        match &self.source {
+            LayoutSource::Builtin(create) => Ok(create()),
+            LayoutSource::File { layout, .. } => match layout {
+                Ok(layout) => Ok(layout.clone()),
+                Err(reason) => Err(DriverError::InvalidLayout(reason.clone())),
+            },
+        }
+    }
+
+    fn answers_to(&self, name: &str) -> bool {
+        self.name == name || self.aliases.iter().any(|alias| alias == name)
+    }
+}
+
+/// Every layout that can be selected by name
+#[derive(Clone, Default)]
+pub struct LayoutRegistry {
+    entries: Vec<LayoutEntry>,
+}
+
+impl LayoutRegistry {
+    /// Registry holding only the built-in layouts
+    pub fn builtin() -> Self {
+        let mut registry = Self::default();
+        for builtin in BUILTIN_LAYOUTS {
+            registry.register(LayoutEntry {
+                name: builtin.name.to_string(),
+                aliases: builtin.aliases.iter().map(|a| a.to_string()).collect(),
+                description: builtin.description.to_string(),
+                touchpad_ids: HARDWARE_TABLE
+                    .iter()
+                    .filter(|entry| entry.layout == builtin.name)
+                    .filter_map(|entry| entry.touchpad_id)
+                    .collect(),
+                source: LayoutSource::Builtin(builtin.create),
+            });
+        }
+        registry
+    }
+
+    /// Built-in layouts overridden by `*.toml` files in `dirs`, highest priority first
+    pub fn with_layout_dirs<P: AsRef<Path>>(dirs: &[P]) -> Self {
+        let mut registry = Self::builtin();
+        for dir in dirs.iter().rev() {
+            registry.register_dir(dir.as_ref());
+        }
+        registry
+    }
+
+    /// Built-in layouts and those in [`LAYOUT_DIRS`], read once per process
+    pub fn installed() -> &'static Self {
+        static INSTALLED: OnceLock<LayoutRegistry> = OnceLock::new();
+        INSTALLED.get_or_init(|| Self::with_layout_dirs(&LAYOUT_DIRS))
+    }
+
+    /// Add a layout, replacing any registered under the same name
+    ///
+    /// The replacement keeps the aliases of the layout it overrides, and its
+    /// description and touchpad IDs when it gives none.
+    pub fn register(&mut self, mut entry: LayoutEntry) {
+        if let Some(index) = self.entries.iter().position(|e| e.name == entry.name) {
+            let replaced = self.entries.remove(index);
+            for alias in replaced.aliases {
+                if !entry.aliases.contains(&alias) {
+                    entry.aliases.push(alias);
+                }
+            }
+            if entry.description.is_empty() {
+                entry.description = replaced.description;
+            }
+            if entry.touchpad_ids.is_empty() {
+                entry.touchpad_ids = replaced.touchpad_ids;
+            }
+        }
+        self.entries.push(entry);
+    }
+
+    /// Register every `<name>.toml` in `dir` under its file name
+    pub fn register_dir(&mut self, dir: &Path) {
+        let Ok(read_dir) = fs::read_dir(dir) else {
+            return;
+        };
+        let mut paths: Vec<PathBuf> = read_dir
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml") && path.is_file())
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let name = name.to_lowercase();
+            let entry = match FileLayout::from_path(&path) {
+                Ok(layout) => LayoutEntry {
+                    name,
+                    aliases: layout.aliases().to_vec(),
+                    description: layout.description().unwrap_or_default().to_string(),
+                    touchpad_ids: layout.touchpad_ids().to_vec(),
+                    source: LayoutSource::File {
+                        path: path.clone(),
+                        layout: Ok(Arc::new(layout)),
+                    },
+                },
+                Err(e) => {
+                    warn!("Invalid layout file {}: {}", path.display(), e);
+                    LayoutEntry {
+                        name,
+                        aliases: Vec::new(),
+                        description: String::new(),
+                        touchpad_ids: Vec::new(),
+                        source: LayoutSource::File {
+                            path: path.clone(),
+                            layout: Err(match e {
+                                DriverError::InvalidLayout(reason) => reason,
+                                other => other.to_string(),
+                            }),
+                        },
+                    }
+                }
+            };
+            self.register(entry);
+        }
+    }
+
+    pub fn entries(&self) -> &[LayoutEntry] {
+        &self.entries
+    }
+
+    /// Entry registered under `name` or, failing that, with `name` as an alias
+    pub fn find(&self, name: &str) -> Option<&LayoutEntry> {
+        let name = name.to_lowercase();
+        self.entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .or_else(|| {
+                self.entries
+                    .iter()
+                    .rev()
+                    .find(|entry| entry.answers_to(&name))
+            })
+    }
+
+    /// Load the layout registered under `name` or an alias
+    pub fn get(&self, name: &str) -> Result<Arc<dyn NumpadLayout>> {
+        match self.find(name) {
+            Some(entry) => entry.load(),
+            None => Err(DriverError::LayoutNotFound {
+                name: name.to_string(),
+                suggestions: self.suggestions(name),
+            }),
+        }
+    }
+
+    /// Up to three registered names or aliases closest to `name`
+    pub fn suggestions(&self, name: &str) -> Vec<String> {
+        let name = name.to_lowercase();
+        let mut candidates: Vec<(usize, &str)> = self
+            .entries
+            .iter()
+            .flat_map(|entry| std::iter::once(&entry.name).chain(&entry.aliases))
+            .map(|candidate| {
+                // A typed prefix such as `g634` counts as a near miss
+                let distance = if candidate.starts_with(&name) && name.len() >= 3 {
+                    1
+                } else {
+                    edit_distance(&name, candidate)
+                };
+                (distance, candidate.as_str())
+            })
+            .filter(|&(distance, candidate)| distance <= (candidate.len() / 3).max(2))
+            .collect();
+        candidates.sort();
+        candidates.dedup_by(|a, b| a.1 == b.1);
+        candidates
+            .into_iter()
+            .take(3)
+            .map(|(_, candidate)| candidate.to_string())
+            .collect()
+    }
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_builtins_by_name_and_alias() {
+        let registry = LayoutRegistry::builtin();
+
+        assert_eq!(registry.get("G634JYR").unwrap().name(), "g634jy");
+        assert_eq!(registry.get("ux425ea").unwrap().name(), "ux433fa");
+        let g634jy = registry.find("g634jy").unwrap();
+        assert_eq!(
+            g634jy.touchpad_ids,
+            [DeviceId {
+                vendor: 0x2808,
+                product: 0x0108
+            }]
+        );
+    }
+
+    #[test]
+    fn suggests_closest_names_for_typos() {
+        let registry = LayoutRegistry::builtin();
+
+        assert_eq!(registry.suggestions("g643jy"), ["g634jy"]);
+        assert_eq!(registry.suggestions("ux58"), ["ux581", "ux581l"]);
+        assert!(registry.suggestions("thinkpad").is_empty());
+
+        let err = registry.get("ga410").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Layout not found: ga410 (did you mean ga401?)"
+        );
+    }
+
+    #[test]
+    fn layout_files_override_builtins_in_priority_order() {
+        let root = std::env::temp_dir().join(format!("layout-registry-{}", std::process::id()));
+        let etc = root.join("etc");
+        let share = root.join("share");
+        fs::create_dir_all(&etc).unwrap();
+        fs::create_dir_all(&share).unwrap();
+        fs::write(
+            share.join("custom.toml"),
+            "name = \"share\"\naliases = [\"mine\"]\ndescription = \"From share\"\ntouchpad_ids = [\"04f3:3101\"]",
+        )
+        .unwrap();
+        fs::write(share.join("g634jy.toml"), "name = \"override\"").unwrap();
+
+        let registry = LayoutRegistry::with_layout_dirs(&[&etc, &share]);
+        assert_eq!(registry.get("mine").unwrap().name(), "share");
+        assert_eq!(registry.get("g634jy").unwrap().name(), "override");
+        // The override keeps the built-in's aliases and metadata
+        assert_eq!(registry.get("g634jyr").unwrap().name(), "override");
+        assert_eq!(
+            registry.find("g634jy").unwrap().description,
+            "ROG Strix SCAR 16 G634JY / G634JYR"
+        );
+        let custom = registry.find("custom").unwrap();
+        assert_eq!(custom.description, "From share");
+        assert_eq!(custom.touchpad_ids[0].to_string(), "04f3:3101");
+
+        fs::write(etc.join("custom.toml"), "name = \"etc\"").unwrap();
+        fs::write(etc.join("broken.toml"), "name = ").unwrap();
+        let registry = LayoutRegistry::with_layout_dirs(&[&etc, &share]);
+        assert_eq!(registry.get("custom").unwrap().name(), "etc");
+        assert_eq!(registry.get("mine").unwrap().name(), "etc");
+        assert!(matches!(
+            registry.get("broken"),
+            Err(DriverError::InvalidLayout(_))
+        ));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,331 @@
+use super::{Action, ActionDefinition, KeyLayer, KeyRegion, NumpadLayout};
+use crate::error::{DriverError, Result};
+use evdev::KeyCode;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// What a remap entry replaces
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemapSource {
+    /// A named region of the numpad layer, e.g. `backspace`
+    Region(String),
+    /// Every region emitting this key, on every layer
+    Key(KeyCode),
+}
+
+/// Single override; an [`Action::Key`] replaces the key, other actions are
+/// performed instead of pressing it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyRemap {
+    pub from: RemapSource,
+    pub action: Action,
+}
+
+impl KeyRemap {
+    /// Parse remap entries such as `KEY_BACKSPACE = "KEY_ESC"` or `backspace = "KEY_KPEQUAL"`
+    ///
+    /// Sources that look like evdev names (`KEY_`/`BTN_`) are key codes, anything
+    /// else is a region name.
+    pub fn parse_map(map: &BTreeMap<String, String>) -> Result<Vec<KeyRemap>> {
+        map.iter()
+            .map(|(from, to)| {
+                Ok(KeyRemap {
+                    from: parse_source(from)?,
+                    action: Action::Key(remap_key_code(from, to)?),
+                })
+            })
+            .collect()
+    }
+
+    /// Parse action entries such as `kp0 = { text = "00" }`
+    pub fn parse_actions(map: &BTreeMap<String, ActionDefinition>) -> Result<Vec<KeyRemap>> {
+        map.iter()
+            .map(|(from, action)| {
+                Ok(KeyRemap {
+                    from: parse_source(from)?,
+                    action: action.parse().map_err(|reason| {
+                        DriverError::InvalidConfig(format!("action {}: {}", from, reason))
+                    })?,
+                })
+            })
+            .collect()
+    }
+}
+
+fn parse_source(from: &str) -> Result<RemapSource> {
+    if from.starts_with("KEY_") || from.starts_with("BTN_") {
+        Ok(RemapSource::Key(remap_key_code(from, from)?))
+    } else {
+        Ok(RemapSource::Region(from.to_string()))
+    }
+}
+
+fn source_name(source: &RemapSource) -> String {
+    match source {
+        RemapSource::Region(name) => name.clone(),
+        RemapSource::Key(key) => format!("{:?}", key),
+    }
+}
+
+fn remap_key_code(entry: &str, name: &str) -> Result<KeyCode> {
+    KeyCode::from_str(name).map_err(|_| {
+        DriverError::InvalidConfig(format!(
+            "remap {}: unknown key name {:?} (expected an evdev name such as KEY_KPEQUAL)",
+            entry, name
+        ))
+    })
+}
+
+/// Keys of the key regions on every layer, which are the keys a remap can
+/// replace; control zones and sliders keep theirs
+pub(crate) fn region_keys(layout: &dyn NumpadLayout) -> Vec<KeyCode> {
+    let layers = layout.layers().iter().map(|layer| layer.regions.as_slice());
+    std::iter::once(layout.regions())
+        .chain(layers)
+        .flatten()
+        .map(|region| region.key)
+        .collect()
+}
+
+/// Layout wrapper that substitutes keys and actions according to a remap table
+///
+/// Geometry, control zones and sliders come from the wrapped layout unchanged.
+pub struct RemappedLayout {
+    inner: Arc<dyn NumpadLayout>,
+    regions: Vec<KeyRegion>,
+    layers: Vec<KeyLayer>,
+}
+
+impl RemappedLayout {
+    pub fn new(inner: Arc<dyn NumpadLayout>, remaps: &[KeyRemap]) -> Result<Self> {
+        let all_keys = inner.all_keys();
+        let region_keys = region_keys(inner.as_ref());
+        for remap in remaps {
+            let (found, elsewhere) = match &remap.from {
+                RemapSource::Region(name) => (
+                    inner.regions().iter().any(|r| r.name == *name),
+                    inner.zones().iter().any(|z| z.name == *name)
+                        || inner.sliders().iter().any(|s| s.name == *name),
+                ),
+                RemapSource::Key(key) => (region_keys.contains(key), all_keys.contains(key)),
+            };
+            if !found && elsewhere {
+                return Err(DriverError::InvalidConfig(format!(
+                    "remap {}: only keys can be remapped, not control zones or sliders of layout {}",
+                    source_name(&remap.from),
+                    inner.name()
+                )));
+            }
+            if !found {
+                let names: Vec<&str> = inner.regions().iter().map(|r| r.name.as_str()).collect();
+                return Err(DriverError::InvalidConfig(format!(
+                    "remap {}: layout {} has no such key or region (regions: {})",
+                    source_name(&remap.from),
+                    inner.name(),
+                    names.join(", ")
+                )));
+            }
+        }
+
+        let remap_regions = |regions: &[KeyRegion], base: bool| -> Vec<KeyRegion> {
+            regions
+                .iter()
+                .map(|region| {
+                    let by_region = remaps.iter().find(|remap| {
+                        base && remap.from == RemapSource::Region(region.name.clone())
+                    });
+                    let by_key = || {
+                        remaps
+                            .iter()
+                            .find(|remap| remap.from == RemapSource::Key(region.key))
+                    };
+                    match by_region.or_else(by_key).map(|remap| &remap.action) {
+                        Some(Action::Key(key)) => {
+                            KeyRegion::new(region.name.clone(), *key, region.region.clone())
+                        }
+                        Some(action) => region.clone().with_action(action.clone()),
+                        None => region.clone(),
+                    }
+                })
+                .collect()
+        };
+
+        Ok(Self {
+            regions: remap_regions(inner.regions(), true),
+            layers: inner
+                .layers()
+                .iter()
+                .map(|layer| {
+                    KeyLayer::new(layer.name.clone(), remap_regions(&layer.regions, false))
+                })
+                .collect(),
+            inner,
+        })
+    }
+}
+
+impl NumpadLayout for RemappedLayout {
+    fn inner(&self) -> Option<&dyn NumpadLayout> {
+        Some(self.inner.as_ref())
+    }
+
+    fn regions(&self) -> &[KeyRegion] {
+        &self.regions
+    }
+
+    fn layers(&self) -> &[KeyLayer] {
+        &self.layers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::{get_layout, FileLayout};
+    use crate::layouts::{zone_action_at, ZoneAction};
+
+    fn remaps(entries: &[(&str, &str)]) -> Result<Vec<KeyRemap>> {
+        KeyRemap::parse_map(
+            &entries
+                .iter()
+                .map(|(from, to)| (from.to_string(), to.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn remaps_by_key_code_and_registers_new_keys() {
+        let layout = get_layout("g634jy").unwrap();
+        let remapped =
+            RemappedLayout::new(layout, &remaps(&[("KEY_BACKSPACE", "KEY_ESC")]).unwrap()).unwrap();
+
+        assert_eq!(remapped.key_at_position(0.87, 0.40), Some(KeyCode::KEY_ESC));
+        assert_eq!(remapped.key_at_position(0.14, 0.15), Some(KeyCode::KEY_KP7));
+        assert!(remapped.all_keys().contains(&KeyCode::KEY_ESC));
+        assert!(!remapped.all_keys().contains(&KeyCode::KEY_BACKSPACE));
+        assert_eq!(
+            zone_action_at(&remapped, 0.87, 0.15),
+            Some(ZoneAction::Toggle)
+        );
+    }
+
+    #[test]
+    fn region_names_target_one_position_on_the_numpad_layer() {
+        let layout = FileLayout::from_toml(
+            r#"
+name = "two-backspaces"
+
+[[keys]]
+key = "KEY_BACKSPACE"
+name = "backspace-top"
+x = [0.5, 1.0]
+y = [0.0, 0.5]
+
+[[keys]]
+key = "KEY_BACKSPACE"
+x = [0.5, 1.0]
+y = [0.5, 1.0]
+
+[[layers]]
+name = "navigation"
+remap = { KEY_BACKSPACE = "KEY_DELETE" }
+"#,
+        )
+        .unwrap();
+        let remapped = RemappedLayout::new(
+            Arc::new(layout),
+            &remaps(&[("backspace-top", "KEY_KPEQUAL"), ("KEY_DELETE", "KEY_ESC")]).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            remapped.key_at_position(0.7, 0.2),
+            Some(KeyCode::KEY_KPEQUAL)
+        );
+        assert_eq!(
+            remapped.key_at_position(0.7, 0.7),
+            Some(KeyCode::KEY_BACKSPACE)
+        );
+        // Region names only apply to the numpad layer; key codes apply everywhere
+        assert_eq!(
+            remapped.key_at_layer_position(1, 0.7, 0.2),
+            Some(KeyCode::KEY_ESC)
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_key_names() {
+        let err = remaps(&[("KEY_BACKSPACE", "KEY_NOPE")]).unwrap_err();
+        assert!(
+            err.to_string().contains("unknown key name \"KEY_NOPE\""),
+            "{err}"
+        );
+
+        let err = remaps(&[("KEY_BACKSPAEC", "KEY_ESC")]).unwrap_err();
+        assert!(err.to_string().contains("KEY_BACKSPAEC"), "{err}");
+    }
+
+    #[test]
+    fn rejects_zone_and_slider_sources() {
+        let layout: Arc<dyn NumpadLayout> = Arc::new(
+            FileLayout::from_toml(
+                r#"
+name = "controls"
+
+[[zones]]
+name = "copy"
+action = { combo = "Ctrl+C" }
+x = [0.0, 0.1]
+y = [0.9, 1.0]
+
+[[sliders]]
+name = "volume"
+action = { decrease = "KEY_VOLUMEDOWN", increase = "KEY_VOLUMEUP", step = 0.1 }
+axis = "vertical"
+x = [0.95, 1.0]
+y = [0.3, 0.9]
+
+[[keys]]
+key = "KEY_KP1"
+x = [0.2, 0.5]
+y = [0.3, 0.6]
+"#,
+            )
+            .unwrap(),
+        );
+
+        for source in ["KEY_VOLUMEUP", "KEY_C", "volume", "copy"] {
+            let err = RemappedLayout::new(layout.clone(), &remaps(&[(source, "KEY_ESC")]).unwrap())
+                .err()
+                .unwrap();
+            assert!(
+                err.to_string()
+                    .contains("not control zones or sliders of layout controls"),
+                "{err}"
+            );
+        }
+        assert!(RemappedLayout::new(layout, &remaps(&[("KEY_KP1", "KEY_1")]).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn rejects_sources_missing_from_layout() {
+        let layout = get_layout("g634jy").unwrap();
+
+        let err = RemappedLayout::new(
+            layout.clone(),
+            &remaps(&[("KEY_KPEQUAL", "KEY_ESC")]).unwrap(),
+        )
+        .err()
+        .unwrap();
+        assert!(
+            err.to_string().contains("layout g634jy has no such key"),
+            "{err}"
+        );
+
+        let err = RemappedLayout::new(layout, &remaps(&[("top-right", "KEY_ESC")]).unwrap())
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("remap top-right"), "{err}");
+    }
+}
@@ -0,0 +1,407 @@
+use super::{ControlZone, NumpadLayout, Region, ZoneAction};
+use std::fmt::Write;
+
+/// ASCII canvas size; characters are roughly twice as tall as they are wide
+pub const ASCII_COLUMNS: usize = 80;
+pub const ASCII_ROWS: usize = 24;
+
+/// SVG canvas size in pixels, matching a 13 cm x 8.5 cm touchpad
+const SVG_WIDTH: f64 = 650.0;
+const SVG_HEIGHT: f64 = 425.0;
+
+/// What a point on the pad does, in the order the driver checks it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Empty,
+    Zone(usize),
+    Slider(usize),
+    Key(usize),
+}
+
+fn cell_at(layout: &dyn NumpadLayout, layer: usize, x: f64, y: f64) -> Cell {
+    if let Some(index) = layout.zones().iter().position(|zone| zone.contains(x, y)) {
+        return Cell::Zone(index);
+    }
+    if let Some(index) = layout
+        .sliders()
+        .iter()
+        .position(|slider| slider.contains(x, y))
+    {
+        return Cell::Slider(index);
+    }
+    layout
+        .layer_regions(layer)
+        .iter()
+        .position(|region| region.contains(x, y))
+        .map_or(Cell::Empty, Cell::Key)
+}
+
+/// Full and short label of a control zone
+fn zone_labels(zone: &ControlZone) -> (String, String) {
+    match zone.action {
+        ZoneAction::Toggle => ("TOGGLE".into(), "TGL".into()),
+        ZoneAction::Calculator => ("CALC".into(), "C".into()),
+        ZoneAction::Brightness => ("BRIGHT".into(), "B".into()),
+        ZoneAction::LayerSwitch => ("LAYER".into(), "L".into()),
+        ZoneAction::Action(_) => {
+            let full = zone.name.to_uppercase();
+            let short = full.chars().take(1).collect();
+            (full, short)
+        }
+    }
+}
+
+/// Full label and a shorter fallback for narrow regions
+fn cell_labels(layout: &dyn NumpadLayout, layer: usize, cell: Cell) -> (String, String) {
+    match cell {
+        Cell::Empty => (String::new(), String::new()),
+        Cell::Zone(index) => zone_labels(&layout.zones()[index]),
+        Cell::Slider(index) => {
+            let full = layout.sliders()[index].name.to_uppercase();
+            let short = full.chars().take(1).collect();
+            (full, short)
+        }
+        Cell::Key(index) => {
+            let full = format!("{:?}", layout.layer_regions(layer)[index].key);
+            let short = full.trim_start_matches("KEY_").to_string();
+            (full, short)
+        }
+    }
+}
+
+/// Draw a layer of the layout as ASCII boxes, sampled at each character
+pub fn render_ascii(
+    layout: &dyn NumpadLayout,
+    layer: usize,
+    columns: usize,
+    rows: usize,
+) -> String {
+    let cells: Vec<Vec<Cell>> = (0..rows)
+        .map(|row| {
+            let y = (row as f64 + 0.5) / rows as f64;
+            (0..columns)
+                .map(|col| cell_at(layout, layer, (col as f64 + 0.5) / columns as f64, y))
+                .collect()
+        })
+        .collect();
+    let get = |row: Option<usize>, col: Option<usize>| -> Option<Cell> {
+        cells.get(row?)?.get(col?).copied()
+    };
+
+    // Each canvas position is the top-left corner of its sample cell; an
+    // edge runs from it wherever the two cells on either side differ. The
+    // extra row and column close the bottom and right edges of the pad.
+    let mut canvas = vec![vec![' '; columns + 1]; rows + 1];
+    for (row, line) in canvas.iter_mut().enumerate() {
+        for (col, ch) in line.iter_mut().enumerate() {
+            let (above, left) = (row.checked_sub(1), col.checked_sub(1));
+            let up_left = get(above, left);
+            let up_right = get(above, Some(col));
+            let down_left = get(Some(row), left);
+            let down_right = get(Some(row), Some(col));
+            let horizontal = up_right != down_right || up_left != down_left;
+            let vertical = up_left != up_right || down_left != down_right;
+            *ch = match (horizontal, vertical) {
+                (true, true) => '+',
+                (true, false) => '-',
+                (false, true) => '|',
+                (false, false) => ' ',
+            };
+        }
+    }
+
+    let mut labelled = Vec::new();
+    for row in 0..rows {
+        for col in 0..columns {
+            let cell = cells[row][col];
+            if cell == Cell::Empty || labelled.contains(&cell) {
+                continue;
+            }
+            labelled.push(cell);
+            place_label(&mut canvas, &cells, cell, cell_labels(layout, layer, cell));
+        }
+    }
+
+    let mut out = String::new();
+    for line in canvas {
+        let line: String = line.into_iter().collect();
+        let _ = writeln!(out, "{}", line.trim_end());
+    }
+    out
+}
+
+/// Centre a label on the middle row of the cell's interior
+fn place_label(
+    canvas: &mut [Vec<char>],
+    cells: &[Vec<Cell>],
+    cell: Cell,
+    labels: (String, String),
+) {
+    let interior: Vec<(usize, usize)> = cells
+        .iter()
+        .enumerate()
+        .flat_map(|(row, line)| {
+            line.iter()
+                .enumerate()
+                .filter(move |(_, c)| **c == cell)
+                .map(move |(col, _)| (row, col))
+        })
+        .filter(|&(row, col)| canvas[row][col] == ' ')
+        .collect();
+    let Some(top) = interior.iter().map(|p| p.0).min() else {
+        return;
+    };
+    let bottom = interior.iter().map(|p| p.0).max().unwrap_or(top);
+    let row = (top + bottom) / 2;
+    let mut row_cols: Vec<usize> = interior
+        .iter()
+        .filter(|p| p.0 == row)
+        .map(|p| p.1)
+        .collect();
+    row_cols.sort_unstable();
+    let Some(&first) = row_cols.first() else {
+        return;
+    };
+
+    // Longest run of free columns on that row
+    let (mut start, mut len, mut run_start, mut run_len) = (first, 0, first, 0);
+    for (i, &col) in row_cols.iter().enumerate() {
+        if i > 0 && col == row_cols[i - 1] + 1 {
+            run_len += 1;
+        } else {
+            run_start = col;
+            run_len = 1;
+        }
+        if run_len > len {
+            start = run_start;
+            len = run_len;
+        }
+    }
+
+    let (full, short) = labels;
+    let label: String = if full.chars().count() <= len {
+        full
+    } else if short.chars().count() <= len {
+        short
+    } else {
+        short.chars().take(len).collect()
+    };
+    let offset = start + len.saturating_sub(label.chars().count()) / 2;
+    for (i, ch) in label.chars().enumerate() {
+        canvas[row][offset + i] = ch;
+    }
+}
+
+/// Append an SVG rect or polygon for the region with the given attributes
+fn draw_region(out: &mut String, region: &Region, style: &str) {
+    let sx = |x: f64| x * SVG_WIDTH;
+    let sy = |y: f64| y * SVG_HEIGHT;
+    match region {
+        Region::Rect { x, y } => {
+            let _ = writeln!(
+                out,
+                r##"  <rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" {}/>"##,
+                sx(x.0),
+                sy(y.0),
+                sx(x.1 - x.0),
+                sy(y.1 - y.0),
+                style
+            );
+        }
+        Region::Polygon(points) => {
+            let points: Vec<String> = points
+                .iter()
+                .map(|&(x, y)| format!("{:.1},{:.1}", sx(x), sy(y)))
+                .collect();
+            let _ = writeln!(
+                out,
+                r##"  <polygon points="{}" {}/>"##,
+                points.join(" "),
+                style
+            );
+        }
+    }
+}
+
+/// Draw a layer of the layout to scale as an SVG document
+pub fn render_svg(layout: &dyn NumpadLayout, layer: usize) -> String {
+    let sx = |x: f64| x * SVG_WIDTH;
+    let sy = |y: f64| y * SVG_HEIGHT;
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="monospace" font-size="11">"#,
+        w = SVG_WIDTH,
+        h = SVG_HEIGHT
+    );
+    let _ = writeln!(
+        out,
+        r##"  <title>{} ({})</title>"##,
+        layout.name(),
+        layout.layer_name(layer)
+    );
+    let _ = writeln!(
+        out,
+        r##"  <rect x="0" y="0" width="{}" height="{}" fill="#f4f4f4" stroke="#333" stroke-width="2"/>"##,
+        SVG_WIDTH, SVG_HEIGHT
+    );
+
+    let mut labels = Vec::new();
+    for region in layout.layer_regions(layer) {
+        draw_region(
+            &mut out,
+            &region.region,
+            r##"fill="#dce8f5" stroke="#333""##,
+        );
+        let (x, y) = region.region.bounds();
+        labels.push((
+            (x.0 + x.1) / 2.0,
+            (y.0 + y.1) / 2.0,
+            format!("{:?}", region.key),
+        ));
+    }
+
+    for zone in layout.zones() {
+        let color = match zone.action {
+            ZoneAction::Toggle => "#f5a623",
+            ZoneAction::Calculator => "#7ed321",
+            ZoneAction::Brightness => "#f8e71c",
+            ZoneAction::LayerSwitch => "#bd10e0",
+            ZoneAction::Action(_) => "#4a90e2",
+        };
+        let style = format!(
+            r##"fill="{}" fill-opacity="0.6" stroke="{}""##,
+            color, color
+        );
+        draw_region(&mut out, &zone.region, &style);
+        let (x, y) = zone.region.bounds();
+        labels.push(((x.0 + x.1) / 2.0, (y.0 + y.1) / 2.0, zone_labels(zone).0));
+    }
+
+    for slider in layout.sliders() {
+        draw_region(
+            &mut out,
+            &slider.region,
+            r##"fill="#50e3c2" fill-opacity="0.6" stroke="#50e3c2""##,
+        );
+        let (x, y) = slider.region.bounds();
+        labels.push((
+            (x.0 + x.1) / 2.0,
+            (y.0 + y.1) / 2.0,
+            slider.name.to_uppercase(),
+        ));
+    }
+
+    for (x, y, label) in labels {
+        let _ = writeln!(
+            out,
+            r##"  <text x="{:.1}" y="{:.1}" text-anchor="middle" dominant-baseline="middle">{}</text>"##,
+            sx(x),
+            sy(y),
+            label
+        );
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::{get_layout, FileLayout};
+
+    fn two_keys() -> FileLayout {
+        FileLayout::from_toml(
+            r#"
+name = "two"
+
+[toggle]
+x = [0.75, 1.0]
+y = [0.0, 0.25]
+
+[calculator]
+x = [0.0, 0.25]
+y = [0.0, 0.35]
+
+[[keys]]
+key = "KEY_KP1"
+x = [0.0, 0.5]
+y = [0.0, 1.0]
+
+[[keys]]
+key = "KEY_KPENTER"
+polygon = [[0.5, 0.25], [1.0, 0.25], [1.0, 1.0]]
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn ascii_draws_boxes_and_labels_each_region() {
+        let art = render_ascii(&two_keys(), 0, 40, 12);
+        let lines: Vec<&str> = art.lines().collect();
+
+        assert_eq!(lines.len(), 13);
+        assert!(lines[0].starts_with("+---"));
+        assert!(lines[0].ends_with('+'));
+        assert!(lines[12].starts_with("+---"));
+        assert!(art.contains("KEY_KP1"), "{art}");
+        assert!(art.contains("TOGGLE"), "{art}");
+        assert!(art.contains("CALC"), "{art}");
+        assert!(art.contains("KPENTER"), "{art}");
+    }
+
+    #[test]
+    fn ascii_labels_every_g634jy_key() {
+        let layout = get_layout("g634jy").unwrap();
+        let art = render_ascii(layout.as_ref(), 0, ASCII_COLUMNS, ASCII_ROWS);
+
+        for key in layout.regions().iter().map(|region| region.key) {
+            let name = format!("{:?}", key);
+            assert!(
+                art.contains(name.trim_start_matches("KEY_")),
+                "{name}\n{art}"
+            );
+        }
+    }
+
+    #[test]
+    fn ascii_centres_non_ascii_labels() {
+        let layout = FileLayout::from_toml(
+            r#"
+name = "euro"
+
+[[zones]]
+name = "€uro"
+action = { text = "€" }
+x = [0.0, 0.5]
+y = [0.0, 1.0]
+"#,
+        )
+        .unwrap();
+
+        // Narrow renders truncate the label by characters without panicking
+        for columns in 4..24 {
+            render_ascii(&layout, 0, columns, 6);
+        }
+        let art = render_ascii(&layout, 0, 14, 6);
+        let line = art.lines().find(|line| line.contains("€URO")).unwrap();
+        assert_eq!(line.chars().position(|c| c == '€'), Some(2), "{art}");
+    }
+
+    #[test]
+    fn svg_draws_regions_and_control_zones_to_scale() {
+        let svg = render_svg(&two_keys(), 0);
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert!(
+            svg.contains(r##"<rect x="0.0" y="0.0" width="325.0" height="425.0" fill="#dce8f5""##)
+        );
+        assert!(svg.contains(r#"<polygon points="325.0,106.2 650.0,106.2 650.0,425.0""#));
+        assert!(svg.contains(r#"<rect x="487.5" y="0.0" width="162.5" height="106.2""#));
+        assert!(svg.contains(">KEY_KPENTER</text>"));
+        assert!(svg.contains(">CALC</text>"));
+        assert!(!svg.contains(">LAYER</text>"));
+    }
+}
@@ -0,0 +1,120 @@
+use super::Region;
+use evdev::KeyCode;
+use serde::Deserialize;
+
+/// Direction a slider is dragged along
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SliderAxis {
+    /// Left to right
+    Horizontal,
+    /// Bottom to top
+    Vertical,
+}
+
+/// What dragging along a slider controls
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SliderTarget {
+    /// Backlight brightness, set from the finger's position along the slider
+    Brightness,
+    /// `decrease` or `increase` clicked once per `step` of the slider's length
+    /// travelled, such as volume or scroll keys
+    Keys {
+        decrease: KeyCode,
+        increase: KeyCode,
+        step: f64,
+    },
+}
+
+impl SliderTarget {
+    /// Keys the virtual keyboard must register for this slider
+    pub fn keys(&self) -> Vec<KeyCode> {
+        match self {
+            SliderTarget::Brightness => Vec::new(),
+            SliderTarget::Keys {
+                decrease, increase, ..
+            } => vec![*decrease, *increase],
+        }
+    }
+}
+
+/// Strip along a pad edge that turns a drag into a continuous control
+#[derive(Debug, Clone, PartialEq)]
+pub struct SliderZone {
+    pub name: String,
+    pub region: Region,
+    pub axis: SliderAxis,
+    pub target: SliderTarget,
+}
+
+impl SliderZone {
+    pub fn new(
+        name: impl Into<String>,
+        region: Region,
+        axis: SliderAxis,
+        target: SliderTarget,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            region,
+            axis,
+            target,
+        }
+    }
+
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        self.region.contains(x, y)
+    }
+
+    /// Position along the slider from 0.0 at its left or bottom end to 1.0 at
+    /// its right or top end, clamped for fingers that slide past the ends
+    pub fn fraction(&self, x: f64, y: f64) -> f64 {
+        let ((x_start, x_end), (y_start, y_end)) = self.region.bounds();
+        let fraction = match self.axis {
+            SliderAxis::Horizontal => (x - x_start) / (x_end - x_start),
+            SliderAxis::Vertical => (y_end - y) / (y_end - y_start),
+        };
+        fraction.clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fraction_runs_left_to_right_and_bottom_to_top() {
+        let top = SliderZone::new(
+            "brightness",
+            Region::Rect {
+                x: (0.2, 0.6),
+                y: (0.0, 0.05),
+            },
+            SliderAxis::Horizontal,
+            SliderTarget::Brightness,
+        );
+        assert_eq!(top.fraction(0.2, 0.02), 0.0);
+        assert!((top.fraction(0.5, 0.02) - 0.75).abs() < 1e-9);
+        assert_eq!(top.fraction(0.9, 0.02), 1.0);
+
+        let right = SliderZone::new(
+            "volume",
+            Region::Rect {
+                x: (0.95, 1.0),
+                y: (0.3, 0.8),
+            },
+            SliderAxis::Vertical,
+            SliderTarget::Keys {
+                decrease: KeyCode::KEY_VOLUMEDOWN,
+                increase: KeyCode::KEY_VOLUMEUP,
+                step: 0.1,
+            },
+        );
+        assert_eq!(right.fraction(0.97, 0.8), 0.0);
+        assert!((right.fraction(0.97, 0.4) - 0.8).abs() < 1e-9);
+        assert_eq!(
+            right.target.keys(),
+            vec![KeyCode::KEY_VOLUMEDOWN, KeyCode::KEY_VOLUMEUP]
+        );
+    }
+}
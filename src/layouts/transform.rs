@@ -0,0 +1,149 @@
+use super::{ControlZone, KeyRegion, NumpadLayout, SliderZone};
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// Mirroring or rotation applied to touch positions before lookup
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum LayoutTransform {
+    /// Positions are used as reported
+    #[default]
+    None,
+    /// Swap left and right, e.g. to put the digits under the left hand
+    MirrorHorizontal,
+    /// Swap top and bottom
+    MirrorVertical,
+    /// Turn the pad upside down
+    #[serde(rename = "rotate-180")]
+    #[value(name = "rotate-180")]
+    Rotate180,
+}
+
+impl LayoutTransform {
+    /// Position on the untransformed layout that a touch at `(x, y)` lands on
+    pub fn apply(self, x: f64, y: f64) -> (f64, f64) {
+        match self {
+            LayoutTransform::None => (x, y),
+            LayoutTransform::MirrorHorizontal => (1.0 - x, y),
+            LayoutTransform::MirrorVertical => (x, 1.0 - y),
+            LayoutTransform::Rotate180 => (1.0 - x, 1.0 - y),
+        }
+    }
+}
+
+/// Layout wrapper that maps touch positions through a [`LayoutTransform`]
+///
+/// Keys, control zones and sliders are looked up at the transformed position,
+/// so the whole layout, icons included, appears mirrored or rotated on the pad.
+pub struct TransformedLayout {
+    inner: Arc<dyn NumpadLayout>,
+    transform: LayoutTransform,
+}
+
+impl TransformedLayout {
+    pub fn new(inner: Arc<dyn NumpadLayout>, transform: LayoutTransform) -> Self {
+        Self { inner, transform }
+    }
+}
+
+impl NumpadLayout for TransformedLayout {
+    fn inner(&self) -> Option<&dyn NumpadLayout> {
+        Some(self.inner.as_ref())
+    }
+
+    fn zone_at_position(&self, x: f64, y: f64) -> Option<&ControlZone> {
+        let (x, y) = self.transform.apply(x, y);
+        self.inner.zone_at_position(x, y)
+    }
+
+    fn slider_at_position(&self, x: f64, y: f64) -> Option<&SliderZone> {
+        let (x, y) = self.transform.apply(x, y);
+        self.inner.slider_at_position(x, y)
+    }
+
+    fn slider_fraction(&self, slider: &SliderZone, x: f64, y: f64) -> f64 {
+        let (x, y) = self.transform.apply(x, y);
+        self.inner.slider_fraction(slider, x, y)
+    }
+
+    fn region_at_layer_position(&self, layer: usize, x: f64, y: f64) -> Option<&KeyRegion> {
+        let (x, y) = self.transform.apply(x, y);
+        self.inner.region_at_layer_position(layer, x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::{zone_action_at, G634jyLayout, HitboxLayout, HitboxTolerance, ZoneAction};
+    use evdev::KeyCode;
+
+    fn g634jy(transform: LayoutTransform) -> TransformedLayout {
+        TransformedLayout::new(Arc::new(G634jyLayout::new()), transform)
+    }
+
+    #[test]
+    fn mirrors_keys_and_zones() {
+        let layout = g634jy(LayoutTransform::MirrorHorizontal);
+
+        assert_eq!(layout.key_at_position(0.86, 0.15), Some(KeyCode::KEY_KP7));
+        assert_eq!(
+            layout.key_at_position(0.13, 0.40),
+            Some(KeyCode::KEY_BACKSPACE)
+        );
+        assert_eq!(
+            zone_action_at(&layout, 0.13, 0.15),
+            Some(ZoneAction::Toggle)
+        );
+        assert_eq!(
+            zone_action_at(&layout, 0.97, 0.03),
+            Some(ZoneAction::Calculator)
+        );
+        assert_eq!(zone_action_at(&layout, 0.03, 0.03), None);
+
+        let layout = g634jy(LayoutTransform::MirrorVertical);
+        assert_eq!(layout.key_at_position(0.14, 0.85), Some(KeyCode::KEY_KP7));
+        assert_eq!(layout.key_at_position(0.14, 0.13), Some(KeyCode::KEY_KP0));
+        assert_eq!(
+            zone_action_at(&layout, 0.87, 0.85),
+            Some(ZoneAction::Toggle)
+        );
+    }
+
+    #[test]
+    fn rotates_keys_and_zones_by_180_degrees() {
+        let layout = g634jy(LayoutTransform::Rotate180);
+
+        assert_eq!(layout.key_at_position(0.86, 0.85), Some(KeyCode::KEY_KP7));
+        assert_eq!(
+            layout.key_at_position(0.13, 0.25),
+            Some(KeyCode::KEY_KPENTER)
+        );
+        assert_eq!(
+            zone_action_at(&layout, 0.13, 0.85),
+            Some(ZoneAction::Toggle)
+        );
+        assert_eq!(
+            zone_action_at(&layout, 0.97, 0.97),
+            Some(ZoneAction::Calculator)
+        );
+        assert_eq!(
+            g634jy(LayoutTransform::None).key_at_position(0.86, 0.85),
+            Some(KeyCode::KEY_KPENTER)
+        );
+    }
+
+    #[test]
+    fn transforms_before_hitbox_tolerance() {
+        let snapping = HitboxLayout::new(
+            Arc::new(G634jyLayout::new()),
+            HitboxTolerance::Snap {
+                max_distance: 0.015,
+            },
+        );
+        let layout = TransformedLayout::new(Arc::new(snapping), LayoutTransform::MirrorHorizontal);
+
+        assert_eq!(layout.key_at_position(0.77, 0.15), Some(KeyCode::KEY_KP7));
+    }
+}
@@ -0,0 +1,128 @@
+use super::{numberpad_zones, uniform_grid_regions, ControlZone, KeyRegion, NumpadLayout};
+use evdev::KeyCode;
+
+/// ZenBook 14 UX433FA / UX425EA layout
+/// LED backlight works using I2C address 0x15
+pub struct Ux433faLayout {
+    regions: Vec<KeyRegion>,
+    zones: Vec<ControlZone>,
+}
+
+impl Ux433faLayout {
+    pub fn new() -> Self {
+        Self {
+            regions: uniform_grid_regions(
+                &[
+                    [
+                        KeyCode::KEY_KP7,
+                        KeyCode::KEY_KP8,
+                        KeyCode::KEY_KP9,
+                        KeyCode::KEY_KPSLASH,
+                        KeyCode::KEY_BACKSPACE,
+                    ],
+                    [
+                        KeyCode::KEY_KP4,
+                        KeyCode::KEY_KP5,
+                        KeyCode::KEY_KP6,
+                        KeyCode::KEY_KPASTERISK,
+                        KeyCode::KEY_BACKSPACE,
+                    ],
+                    [
+                        KeyCode::KEY_KP1,
+                        KeyCode::KEY_KP2,
+                        KeyCode::KEY_KP3,
+                        KeyCode::KEY_KPMINUS,
+                        KeyCode::KEY_KPENTER,
+                    ],
+                    [
+                        KeyCode::KEY_KP0,
+                        KeyCode::KEY_KP0,
+                        KeyCode::KEY_KPDOT,
+                        KeyCode::KEY_KPPLUS,
+                        KeyCode::KEY_KPENTER,
+                    ],
+                ],
+                0.0,
+            ),
+            zones: numberpad_zones(),
+        }
+    }
+}
+
+impl Default for Ux433faLayout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NumpadLayout for Ux433faLayout {
+    fn name(&self) -> &str {
+        "ux433fa"
+    }
+
+    fn regions(&self) -> &[KeyRegion] {
+        &self.regions
+    }
+
+    fn zones(&self) -> &[ControlZone] {
+        &self.zones
+    }
+
+    fn i2c_address(&self) -> Option<u8> {
+        Some(0x15)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::{zone_action_at, ZoneAction};
+
+    fn key_at(x: f64, y: f64) -> Option<KeyCode> {
+        Ux433faLayout::new().key_at_position(x, y)
+    }
+
+    #[test]
+    fn maps_ux433fa_uniform_grid() {
+        assert_eq!(key_at(0.10, 0.10), Some(KeyCode::KEY_KP7));
+        assert_eq!(key_at(0.30, 0.10), Some(KeyCode::KEY_KP8));
+        assert_eq!(key_at(0.50, 0.10), Some(KeyCode::KEY_KP9));
+        assert_eq!(key_at(0.70, 0.10), Some(KeyCode::KEY_KPSLASH));
+        assert_eq!(key_at(0.90, 0.30), Some(KeyCode::KEY_BACKSPACE));
+        assert_eq!(key_at(0.10, 0.60), Some(KeyCode::KEY_KP1));
+        assert_eq!(key_at(0.70, 0.60), Some(KeyCode::KEY_KPMINUS));
+        assert_eq!(key_at(0.90, 0.60), Some(KeyCode::KEY_KPENTER));
+    }
+
+    #[test]
+    fn maps_ux433fa_wide_zero_and_tall_enter() {
+        assert_eq!(key_at(0.10, 0.90), Some(KeyCode::KEY_KP0));
+        assert_eq!(key_at(0.30, 0.90), Some(KeyCode::KEY_KP0));
+        assert_eq!(key_at(0.50, 0.90), Some(KeyCode::KEY_KPDOT));
+        assert_eq!(key_at(0.70, 0.90), Some(KeyCode::KEY_KPPLUS));
+        assert_eq!(key_at(0.90, 0.90), Some(KeyCode::KEY_KPENTER));
+    }
+
+    #[test]
+    fn detects_ux433fa_icon_corners() {
+        let layout = Ux433faLayout::new();
+
+        assert_eq!(
+            zone_action_at(&layout, 0.97, 0.05),
+            Some(ZoneAction::Toggle)
+        );
+        assert_ne!(
+            zone_action_at(&layout, 0.90, 0.05),
+            Some(ZoneAction::Toggle)
+        );
+        assert_eq!(
+            zone_action_at(&layout, 0.03, 0.03),
+            Some(ZoneAction::Calculator)
+        );
+        assert_ne!(
+            zone_action_at(&layout, 0.10, 0.03),
+            Some(ZoneAction::Calculator)
+        );
+        assert_eq!(layout.i2c_address(), Some(0x15));
+    }
+}
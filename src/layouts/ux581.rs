@@ -0,0 +1,120 @@
+use super::{
+    numberpad_zones, percent_key, uniform_grid_regions, ControlZone, KeyRegion, NumpadLayout,
+};
+use evdev::KeyCode;
+
+/// ZenBook Pro Duo UX581 layout
+/// LED backlight works using I2C address 0x15
+/// Four columns with an extra `=`/`%`/Backspace row at the top; `%` types Shift+5
+pub struct Ux581Layout {
+    regions: Vec<KeyRegion>,
+    zones: Vec<ControlZone>,
+}
+
+impl Ux581Layout {
+    pub fn new() -> Self {
+        let mut regions = uniform_grid_regions(
+            &[
+                [
+                    KeyCode::KEY_KPEQUAL,
+                    KeyCode::KEY_5,
+                    KeyCode::KEY_BACKSPACE,
+                    KeyCode::KEY_BACKSPACE,
+                ],
+                [
+                    KeyCode::KEY_KP7,
+                    KeyCode::KEY_KP8,
+                    KeyCode::KEY_KP9,
+                    KeyCode::KEY_KPSLASH,
+                ],
+                [
+                    KeyCode::KEY_KP4,
+                    KeyCode::KEY_KP5,
+                    KeyCode::KEY_KP6,
+                    KeyCode::KEY_KPASTERISK,
+                ],
+                [
+                    KeyCode::KEY_KP1,
+                    KeyCode::KEY_KP2,
+                    KeyCode::KEY_KP3,
+                    KeyCode::KEY_KPMINUS,
+                ],
+                [
+                    KeyCode::KEY_KP0,
+                    KeyCode::KEY_KPDOT,
+                    KeyCode::KEY_KPENTER,
+                    KeyCode::KEY_KPPLUS,
+                ],
+            ],
+            0.0,
+        );
+        percent_key(&mut regions);
+
+        Self {
+            regions,
+            zones: numberpad_zones(),
+        }
+    }
+}
+
+impl Default for Ux581Layout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NumpadLayout for Ux581Layout {
+    fn name(&self) -> &str {
+        "ux581"
+    }
+
+    fn regions(&self) -> &[KeyRegion] {
+        &self.regions
+    }
+
+    fn zones(&self) -> &[ControlZone] {
+        &self.zones
+    }
+
+    fn i2c_address(&self) -> Option<u8> {
+        Some(0x15)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::{Action, KeyStroke};
+
+    fn key_at(x: f64, y: f64) -> Option<KeyCode> {
+        Ux581Layout::new().key_at_position(x, y)
+    }
+
+    #[test]
+    fn maps_ux581_top_control_row() {
+        assert_eq!(key_at(0.10, 0.10), Some(KeyCode::KEY_KPEQUAL));
+        assert_eq!(
+            Ux581Layout::new().action_at_layer_position(0, 0.40, 0.10),
+            Some(Action::Combo(KeyStroke::new(
+                &[KeyCode::KEY_LEFTSHIFT],
+                KeyCode::KEY_5
+            )))
+        );
+        assert_eq!(key_at(0.60, 0.10), Some(KeyCode::KEY_BACKSPACE));
+        assert_eq!(key_at(0.85, 0.15), Some(KeyCode::KEY_BACKSPACE));
+    }
+
+    #[test]
+    fn maps_ux581_four_column_grid() {
+        assert_eq!(key_at(0.10, 0.30), Some(KeyCode::KEY_KP7));
+        assert_eq!(key_at(0.40, 0.50), Some(KeyCode::KEY_KP5));
+        assert_eq!(key_at(0.60, 0.70), Some(KeyCode::KEY_KP3));
+        assert_eq!(key_at(0.90, 0.30), Some(KeyCode::KEY_KPSLASH));
+        assert_eq!(key_at(0.90, 0.50), Some(KeyCode::KEY_KPASTERISK));
+        assert_eq!(key_at(0.90, 0.70), Some(KeyCode::KEY_KPMINUS));
+        assert_eq!(key_at(0.10, 0.90), Some(KeyCode::KEY_KP0));
+        assert_eq!(key_at(0.40, 0.90), Some(KeyCode::KEY_KPDOT));
+        assert_eq!(key_at(0.60, 0.90), Some(KeyCode::KEY_KPENTER));
+        assert_eq!(key_at(0.90, 0.90), Some(KeyCode::KEY_KPPLUS));
+    }
+}
@@ -0,0 +1,394 @@
+use super::{Action, NumpadLayout};
+use evdev::KeyCode;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// Default sampling step in normalized units (200 x 200 samples)
+pub const DEFAULT_VALIDATION_STEP: f64 = 0.005;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// Single problem found in a layout
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Result of sampling a layout's hitboxes over a grid
+#[derive(Debug)]
+pub struct ValidationReport {
+    pub layout: String,
+    pub samples: usize,
+    pub issues: Vec<Issue>,
+    /// Share of the surface that is neither a key nor a control zone, per layer
+    pub dead_share: Vec<(String, f64)>,
+}
+
+impl ValidationReport {
+    pub fn has_errors(&self) -> bool {
+        self.issues
+            .iter()
+            .any(|issue| issue.severity == Severity::Error)
+    }
+
+    fn push(&mut self, severity: Severity, message: String) {
+        self.issues.push(Issue { severity, message });
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Layout {} ({} samples per layer)",
+            self.layout, self.samples
+        )?;
+        for (layer, share) in &self.dead_share {
+            writeln!(f, "  layer {}: {:.1}% dead", layer, share * 100.0)?;
+        }
+        for issue in &self.issues {
+            let label = match issue.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            };
+            writeln!(f, "  {}: {}", label, issue.message)?;
+        }
+        if self.issues.is_empty() {
+            writeln!(f, "  no issues found")?;
+        }
+        Ok(())
+    }
+}
+
+/// Sample the layout every `step` units and report geometry problems
+///
+/// Overlaps are measured on the drawn regions; everything else follows the
+/// layout's own lookup, so hitbox tolerance and transforms are taken into account.
+pub fn validate_layout(layout: &dyn NumpadLayout, step: f64) -> ValidationReport {
+    let steps = (1.0 / step.clamp(0.0005, 0.5)).round() as usize;
+    let samples = steps * steps;
+    let share = |count: usize| count as f64 / samples as f64 * 100.0;
+    let all_keys = layout.all_keys();
+
+    let mut report = ValidationReport {
+        layout: layout.name().to_string(),
+        samples,
+        issues: Vec::new(),
+        dead_share: Vec::new(),
+    };
+    let mut hit_keys: BTreeSet<KeyCode> = BTreeSet::new();
+
+    for layer in 0..layout.layer_count() {
+        let layer_name = layout.layer_name(layer).to_string();
+        let regions = layout.layer_regions(layer);
+        let mut overlaps: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+        let mut zone_overlaps: BTreeMap<(String, String), usize> = BTreeMap::new();
+        let mut unregistered: BTreeMap<String, usize> = BTreeMap::new();
+        let mut dead = 0;
+
+        for row in 0..steps {
+            let y = (row as f64 + 0.5) / steps as f64;
+            for col in 0..steps {
+                let x = (col as f64 + 0.5) / steps as f64;
+
+                let inside: Vec<usize> = regions
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, region)| region.contains(x, y))
+                    .map(|(index, _)| index)
+                    .collect();
+                for (i, &a) in inside.iter().enumerate() {
+                    for &b in &inside[i + 1..] {
+                        *overlaps.entry((a, b)).or_default() += 1;
+                    }
+                }
+
+                let zone = layout.zone_at_position(x, y);
+                if let Some(zone) = zone {
+                    hit_keys.extend(zone.action.keys());
+                }
+                let slider = layout.slider_at_position(x, y);
+                if let Some(slider) = slider {
+                    hit_keys.extend(slider.target.keys());
+                }
+                // Zones and sliders both take touches before the keys
+                let shadow = zone
+                    .map(|zone| &zone.name)
+                    .or(slider.map(|slider| &slider.name));
+                let Some(region) = layout.region_at_layer_position(layer, x, y) else {
+                    if shadow.is_none() {
+                        dead += 1;
+                    }
+                    continue;
+                };
+                hit_keys.insert(region.key);
+                hit_keys.extend(region.action.iter().flat_map(Action::keys));
+                let key = format!("{:?}", region.key);
+                if !all_keys.contains(&region.key) {
+                    *unregistered.entry(key.clone()).or_default() += 1;
+                }
+                if let Some(shadow) = shadow {
+                    *zone_overlaps.entry((shadow.clone(), key)).or_default() += 1;
+                }
+            }
+        }
+
+        for ((a, b), count) in overlaps {
+            report.push(
+                Severity::Error,
+                format!(
+                    "layer {}: regions {} and {} overlap on {:.2}% of the surface",
+                    layer_name,
+                    regions[a].name,
+                    regions[b].name,
+                    share(count)
+                ),
+            );
+        }
+        for ((zone, key), count) in zone_overlaps {
+            report.push(
+                Severity::Warning,
+                format!(
+                    "layer {}: {} overlaps the {} zone on {:.2}% of the surface and is shadowed there",
+                    layer_name,
+                    key,
+                    zone,
+                    share(count)
+                ),
+            );
+        }
+        for (key, count) in unregistered {
+            report.push(
+                Severity::Error,
+                format!(
+                    "layer {}: {} is returned on {:.2}% of the surface but missing from all_keys",
+                    layer_name,
+                    key,
+                    share(count)
+                ),
+            );
+        }
+        report
+            .dead_share
+            .push((layer_name, dead as f64 / samples as f64));
+    }
+
+    for key in all_keys {
+        if !hit_keys.contains(&key) {
+            report.push(
+                Severity::Warning,
+                format!("{:?} is in all_keys but can never be hit", key),
+            );
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::{
+        get_layout, ControlZone, FileLayout, HitboxLayout, HitboxTolerance, KeyRegion,
+    };
+    use std::sync::Arc;
+
+    struct UnregisteredLayout {
+        regions: Vec<KeyRegion>,
+    }
+
+    impl NumpadLayout for UnregisteredLayout {
+        fn name(&self) -> &str {
+            "unregistered"
+        }
+
+        fn regions(&self) -> &[KeyRegion] {
+            &self.regions
+        }
+
+        fn zones(&self) -> &[ControlZone] {
+            &[]
+        }
+
+        fn all_keys(&self) -> Vec<KeyCode> {
+            vec![KeyCode::KEY_KP1, KeyCode::KEY_KP9]
+        }
+    }
+
+    fn messages(report: &ValidationReport, severity: Severity) -> Vec<&str> {
+        report
+            .issues
+            .iter()
+            .filter(|issue| issue.severity == severity)
+            .map(|issue| issue.message.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn builtin_layouts_have_no_errors() {
+        for name in ["g634jy", "ux433fa", "ux581", "m433ia", "ga401", "gx701"] {
+            let layout = get_layout(name).unwrap();
+            let report = validate_layout(layout.as_ref(), 0.01);
+
+            assert!(!report.has_errors(), "{report}");
+        }
+    }
+
+    #[test]
+    fn reports_overlapping_keys_and_toggle_shadowing() {
+        let layout = FileLayout::from_toml(
+            r#"
+name = "overlap"
+
+[toggle]
+x = [0.9, 1.0]
+y = [0.0, 0.1]
+
+[[sliders]]
+action = "brightness"
+axis = "horizontal"
+x = [0.1, 0.4]
+y = [0.0, 0.1]
+
+[[keys]]
+key = "KEY_KP1"
+x = [0.0, 0.6]
+y = [0.0, 1.0]
+
+[[keys]]
+key = "KEY_KP2"
+x = [0.5, 1.0]
+y = [0.0, 1.0]
+"#,
+        )
+        .unwrap();
+        let report = validate_layout(&layout, 0.01);
+
+        assert!(report.has_errors());
+        let errors = messages(&report, Severity::Error);
+        assert_eq!(errors.len(), 1);
+        assert!(
+            errors[0].contains("kp1 and kp2 overlap on 10.00%"),
+            "{report}"
+        );
+        let warnings = messages(&report, Severity::Warning);
+        assert!(
+            warnings
+                .iter()
+                .any(|w| w.contains("KEY_KP2 overlaps the toggle zone")),
+            "{report}"
+        );
+        assert!(
+            warnings
+                .iter()
+                .any(|w| w.contains("KEY_KP1 overlaps the brightness zone")),
+            "{report}"
+        );
+    }
+
+    #[test]
+    fn reports_unregistered_and_unreachable_keys() {
+        let layout = UnregisteredLayout {
+            regions: vec![
+                KeyRegion::rect(KeyCode::KEY_KP1, (0.0, 0.5), (0.0, 1.0)),
+                KeyRegion::rect(KeyCode::KEY_KP2, (0.5, 1.0), (0.0, 1.0)),
+            ],
+        };
+        let report = validate_layout(&layout, 0.01);
+
+        let errors = messages(&report, Severity::Error);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("KEY_KP2 is returned"), "{report}");
+        let warnings = messages(&report, Severity::Warning);
+        assert!(
+            warnings
+                .iter()
+                .any(|w| w.contains("KEY_KP9 is in all_keys but can never be hit")),
+            "{report}"
+        );
+    }
+
+    /// Sends the right half of the pad to a key none of its regions has
+    struct RedirectingLayout {
+        regions: Vec<KeyRegion>,
+        hidden: KeyRegion,
+    }
+
+    impl NumpadLayout for RedirectingLayout {
+        fn name(&self) -> &str {
+            "redirecting"
+        }
+
+        fn regions(&self) -> &[KeyRegion] {
+            &self.regions
+        }
+
+        fn zones(&self) -> &[ControlZone] {
+            &[]
+        }
+
+        fn region_at_layer_position(&self, _layer: usize, x: f64, y: f64) -> Option<&KeyRegion> {
+            if x >= 0.5 {
+                Some(&self.hidden)
+            } else {
+                self.regions.iter().find(|region| region.contains(x, y))
+            }
+        }
+    }
+
+    #[test]
+    fn samples_through_the_layout_lookup() {
+        let layout = RedirectingLayout {
+            regions: vec![KeyRegion::rect(KeyCode::KEY_KP1, (0.0, 0.5), (0.0, 1.0))],
+            hidden: KeyRegion::rect(KeyCode::KEY_KPEQUAL, (0.5, 1.0), (0.0, 1.0)),
+        };
+        let report = validate_layout(&layout, 0.01);
+
+        let errors = messages(&report, Severity::Error);
+        assert_eq!(errors.len(), 1);
+        assert!(
+            errors[0].contains("KEY_KPEQUAL is returned on 50.00%"),
+            "{report}"
+        );
+        assert_eq!(report.dead_share[0].1, 0.0);
+    }
+
+    #[test]
+    fn measures_dead_surface_share() {
+        let layout = FileLayout::from_toml(
+            r#"
+name = "half"
+
+[toggle]
+x = [0.9, 1.0]
+y = [0.9, 1.0]
+
+[[keys]]
+key = "KEY_KP1"
+x = [0.0, 0.5]
+y = [0.0, 1.0]
+"#,
+        )
+        .unwrap();
+        let report = validate_layout(&layout, 0.01);
+
+        let (layer, dead) = &report.dead_share[0];
+        assert_eq!(layer, "numpad");
+        // Half the pad minus the toggle zone and the default calculator corner
+        assert!((dead - 0.49).abs() < 0.001, "{dead}");
+        assert!(!report.has_errors());
+
+        let snapping = HitboxLayout::new(
+            Arc::new(layout),
+            HitboxTolerance::Snap { max_distance: 0.1 },
+        );
+        let (_, dead) = validate_layout(&snapping, 0.01).dead_share[0];
+        // Touches up to 0.1 right of the key now reach it
+        assert!((dead - 0.39).abs() < 0.001, "{dead}");
+    }
+}
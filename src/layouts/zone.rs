@@ -0,0 +1,115 @@
+use super::{Action, Band, Region};
+use evdev::KeyCode;
+
+/// What touching a control zone does
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ZoneAction {
+    /// Turn the numpad on or off
+    Toggle,
+    /// Cycle the backlight while the numpad is on, open the calculator while it is off
+    Calculator,
+    /// Cycle the backlight while the numpad is on
+    Brightness,
+    /// Cycle key layers while the numpad is on
+    LayerSwitch,
+    /// Perform a key action while the numpad is on
+    Action(Action),
+}
+
+impl ZoneAction {
+    /// Parse a built-in zone action name as written in layout files
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "toggle" => Some(ZoneAction::Toggle),
+            "calculator" => Some(ZoneAction::Calculator),
+            "brightness" => Some(ZoneAction::Brightness),
+            "layer_switch" => Some(ZoneAction::LayerSwitch),
+            _ => None,
+        }
+    }
+
+    /// Name given to zones that do not set one
+    pub fn name(&self) -> &'static str {
+        match self {
+            ZoneAction::Toggle => "toggle",
+            ZoneAction::Calculator => "calculator",
+            ZoneAction::Brightness => "brightness",
+            ZoneAction::LayerSwitch => "layer_switch",
+            ZoneAction::Action(_) => "action",
+        }
+    }
+
+    /// Keys the virtual keyboard must register for this zone
+    pub fn keys(&self) -> Vec<KeyCode> {
+        match self {
+            ZoneAction::Action(action) => action.keys(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Area outside the keys, such as a printed icon, that triggers an action
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlZone {
+    pub name: String,
+    pub region: Region,
+    pub action: ZoneAction,
+}
+
+impl ControlZone {
+    pub fn new(name: impl Into<String>, region: Region, action: ZoneAction) -> Self {
+        Self {
+            name: name.into(),
+            region,
+            action,
+        }
+    }
+
+    /// Rectangular zone named after its action
+    pub fn rect(action: ZoneAction, x: Band, y: Band) -> Self {
+        Self::new(action.name(), Region::Rect { x, y }, action)
+    }
+
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        self.region.contains(x, y)
+    }
+}
+
+/// Top-right toggle zone used when a layout does not place its own
+pub fn default_toggle_zone() -> ControlZone {
+    ControlZone::rect(ZoneAction::Toggle, (0.80, 1.0), (0.0, 0.25))
+}
+
+/// Top-left calculator icon shared by every supported model
+pub fn default_calculator_zone() -> ControlZone {
+    ControlZone::rect(ZoneAction::Calculator, (0.0, 0.06), (0.0, 0.07))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::{zone_action_at, FileLayout};
+
+    #[test]
+    fn corner_detection_uses_expected_zones() {
+        let layout = FileLayout::from_toml("name = \"defaults\"").unwrap();
+
+        assert_eq!(
+            zone_action_at(&layout, 0.90, 0.10),
+            Some(ZoneAction::Toggle)
+        );
+        assert_eq!(
+            zone_action_at(&layout, 0.05, 0.05),
+            Some(ZoneAction::Calculator)
+        );
+        assert_eq!(zone_action_at(&layout, 0.50, 0.50), None);
+        // Zone bands include their start, so the toggle begins at x = 0.80
+        assert_eq!(
+            zone_action_at(&layout, 0.80, 0.10),
+            Some(ZoneAction::Toggle)
+        );
+        assert_eq!(zone_action_at(&layout, 0.79, 0.10), None);
+        assert_eq!(zone_action_at(&layout, 0.90, 0.25), None);
+        assert_eq!(zone_action_at(&layout, 0.06, 0.05), None);
+    }
+}
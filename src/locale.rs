@@ -0,0 +1,149 @@
+use crate::error::{DriverError, Result};
+use crate::layouts::{Action, ActionDefinition, KeyRemap, RemapSource};
+use evdev::KeyCode;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Languages whose numbers use a decimal comma
+const DECIMAL_COMMA_LANGUAGES: &[&str] = &[
+    "bg", "ca", "cs", "da", "de", "el", "es", "et", "fi", "fr", "hr", "hu", "id", "is", "it", "lt",
+    "lv", "nb", "nl", "nn", "no", "pl", "pt", "ro", "ru", "sk", "sl", "sr", "sv", "tr", "uk", "vi",
+];
+
+/// Regions of those languages that write a decimal point instead
+const DECIMAL_POINT_REGIONS: &[&str] = &["de_CH", "it_CH", "es_MX", "es_US"];
+
+/// `[locale]` section of the configuration file
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LocaleDefinition {
+    /// Locale such as `de_DE` or `fr_FR.UTF-8`, picks the decimal separator
+    name: Option<String>,
+    /// `dot`, `kpcomma` or text typed by the dot key; overrides `name`
+    decimal_separator: Option<String>,
+    /// Keypad key code -> action, for keys whose symbol differs
+    #[serde(default)]
+    keys: BTreeMap<String, ActionDefinition>,
+}
+
+/// Keypad output adjusted to the installation's locale
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Locale {
+    pub name: Option<String>,
+    /// Overrides for keypad keys; keys the layout lacks are skipped
+    pub remap: Vec<KeyRemap>,
+}
+
+impl Locale {
+    pub fn from_definition(definition: &LocaleDefinition) -> Result<Self> {
+        let mut remap = KeyRemap::parse_actions(&definition.keys)?;
+        for entry in &remap {
+            if let RemapSource::Region(name) = &entry.from {
+                return Err(DriverError::InvalidConfig(format!(
+                    "locale key {} must be a key code such as KEY_KPASTERISK",
+                    name
+                )));
+            }
+        }
+
+        let separator = match (&definition.decimal_separator, &definition.name) {
+            (Some(separator), _) => decimal_separator_action(separator)?,
+            // Left to the keymap, as typed text assumes US positions and
+            // would come out as another symbol on e.g. AZERTY
+            (None, Some(name)) if uses_decimal_comma(name) => {
+                Some(Action::Key(KeyCode::KEY_KPCOMMA))
+            }
+            (None, _) => None,
+        };
+        let dot = RemapSource::Key(KeyCode::KEY_KPDOT);
+        if let Some(action) = separator.filter(|_| !remap.iter().any(|entry| entry.from == dot)) {
+            remap.push(KeyRemap { from: dot, action });
+        }
+
+        Ok(Self {
+            name: definition.name.clone(),
+            remap,
+        })
+    }
+}
+
+fn decimal_separator_action(separator: &str) -> Result<Option<Action>> {
+    match separator {
+        "dot" => Ok(None),
+        "kpcomma" => Ok(Some(Action::Key(KeyCode::KEY_KPCOMMA))),
+        text => ActionDefinition::Text(text.to_string())
+            .parse()
+            .map(Some)
+            .map_err(|reason| {
+                DriverError::InvalidConfig(format!("locale decimal_separator: {}", reason))
+            }),
+    }
+}
+
+/// Returns true when the locale writes decimals with a comma, e.g. `de_DE.UTF-8`
+pub fn uses_decimal_comma(name: &str) -> bool {
+    let name = name.split(['.', '@']).next().unwrap_or_default();
+    let language = name.split(['_', '-']).next().unwrap_or_default();
+    let region = name.replace('-', "_");
+
+    DECIMAL_COMMA_LANGUAGES.contains(&language.to_ascii_lowercase().as_str())
+        && !DECIMAL_POINT_REGIONS
+            .iter()
+            .any(|r| r.eq_ignore_ascii_case(&region))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locale(source: &str) -> Result<Locale> {
+        let definition: LocaleDefinition = toml::from_str(source).unwrap();
+        Locale::from_definition(&definition)
+    }
+
+    #[test]
+    fn detects_decimal_comma_locales() {
+        assert!(uses_decimal_comma("de_DE.UTF-8"));
+        assert!(uses_decimal_comma("fr"));
+        assert!(uses_decimal_comma("pt-BR"));
+        assert!(!uses_decimal_comma("de_CH.UTF-8"));
+        assert!(!uses_decimal_comma("en_US"));
+        assert!(!uses_decimal_comma("C"));
+    }
+
+    #[test]
+    fn decimal_separator_overrides_locale_name() {
+        let dot = RemapSource::Key(KeyCode::KEY_KPDOT);
+
+        assert_eq!(
+            locale("name = \"de_DE\"").unwrap().remap,
+            vec![KeyRemap {
+                from: dot.clone(),
+                action: Action::Key(KeyCode::KEY_KPCOMMA)
+            }]
+        );
+        assert_eq!(
+            locale("name = \"de_DE\"\ndecimal_separator = \",\"")
+                .unwrap()
+                .remap,
+            vec![KeyRemap {
+                from: dot,
+                action: Action::Text(",".to_string())
+            }]
+        );
+        assert!(locale("name = \"de_DE\"\ndecimal_separator = \"dot\"")
+            .unwrap()
+            .remap
+            .is_empty());
+        assert!(locale("decimal_separator = \"\"").is_err());
+    }
+
+    #[test]
+    fn keys_must_be_key_codes() {
+        let parsed = locale("keys = { KEY_KPASTERISK = { text = \"×\" } }").unwrap();
+        assert_eq!(parsed.remap[0].action, Action::Text("×".to_string()));
+
+        let err = locale("keys = { kpasterisk = { text = \"×\" } }").unwrap_err();
+        assert!(err.to_string().contains("must be a key code"), "{err}");
+    }
+}
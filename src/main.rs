@@ -1,37 +1,70 @@
 use anyhow::{Context, Result};
 use cli::{parse_cli, CliCommand, RunArgs};
-use evdev::{AbsoluteAxisCode, KeyCode, LedCode, SynchronizationCode};
+use config::Config;
+use evdev::{EventType, KeyCode, LedCode, SynchronizationCode};
 use log::{debug, error, info, warn};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+mod calibrate;
 mod cli;
+mod config;
 mod device;
 mod error;
 mod i2c;
 mod input;
 mod layouts;
+mod locale;
 mod numpad;
 
-use device::detect_devices;
-use i2c::{try_create_led_controller, LedController};
-use input::{TouchpadBounds, TouchpadReader, VirtualKeyboard};
-use layouts::{get_layout, NumpadLayout};
-use numpad::{Corner, NumpadState, TouchPosition};
+use device::{detect_devices, read_product_name, select_hardware, InputDeviceInfo, AUTO_MODEL};
+use i2c::{try_create_led_controller, FlashKind, LedController, LedFlash};
+use input::{TouchpadBounds, TouchpadReader, VirtualKeyboard, VirtualTouchpad};
+use layouts::{get_layout, Action, LayoutTransform, NumpadLayout, ZoneAction};
+use numpad::{
+    Activation, ActivationGesture, Contact, ContactChange, KeyRepeat, Lift, NumpadState,
+    Passthrough, PressMode, PressureGate, SlideTracker, SliderStep, SliderTracker, TapSettings,
+    TouchPosition,
+};
 
 /// Runtime context holding all mutable driver state
 struct DriverContext<'a> {
     state: NumpadState,
     virtual_kb: VirtualKeyboard,
     led: Option<LedController>,
+    /// Backlight pulses confirming keys and dead-zone touches
+    flash: Option<LedFlash>,
     touchpad: TouchpadReader,
     layout: &'a dyn NumpadLayout,
     bounds: TouchpadBounds,
-    pending_finger_event: Option<i32>,
+    /// Keys wait for the finger to lift and are only sent for taps
+    tap: Option<TapSettings>,
+    /// Whether keys are sent where the finger lands or lifts; set with a
+    /// tracker when keys follow the finger across hitboxes
+    press: PressMode,
+    slides: Option<SlideTracker>,
+    /// Holds keys back until the finger presses hard enough
+    pressure: Option<PressureGate>,
+    /// Pointer input forwarded while the numpad has the touchpad grabbed;
+    /// both are set in passthrough mode
+    passthrough: Option<Passthrough>,
+    virtual_pointer: Option<VirtualTouchpad>,
+    /// Set when the driver repeats held keys itself; keys are then clicked
+    /// rather than held
+    key_repeat: Option<KeyRepeat>,
+    /// Recognizes the gesture in the toggle zone that turns the numpad on or off
+    activation: Activation,
+    /// Fingers dragging along the layout's edge sliders
+    sliders: SliderTracker,
     numlock_was_on: Option<bool>,
     numlock_toggled_by_driver: bool,
 }
 
+/// Layout used when `auto` finds no hardware table entry
+const AUTO_FALLBACK_MODEL: &str = "g634jy";
+const AUTO_DETECT_TRY_TIMES: u32 = 5;
+const AUTO_DETECT_TRY_SLEEP_MS: u64 = 100;
+
 static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
 
 fn main() -> Result<()> {
@@ -50,15 +83,71 @@ fn run_driver(args: RunArgs) -> Result<()> {
     info!("Starting ASUS Touchpad Numpad Driver");
     info!("Model: {}", args.model);
 
-    // Get layout
-    let layout = get_layout(&args.model).context("Failed to load layout")?;
+    let mut config =
+        Config::load(args.config.as_deref()).context("Failed to load configuration")?;
+    if let Some(transform) = args.transform {
+        config.transform = transform;
+    }
+    if args.passthrough {
+        config.passthrough = true;
+    }
+
+    let (layout, devices, auto_i2c_address) = if args.model.eq_ignore_ascii_case(AUTO_MODEL) {
+        // Detect devices first so the touchpad ID can pick the layout
+        let devices = detect_devices(
+            AUTO_DETECT_TRY_TIMES,
+            Duration::from_millis(AUTO_DETECT_TRY_SLEEP_MS),
+        )
+        .context("Failed to detect devices")?;
+        let (model, i2c_address) = auto_select_model(&devices.touchpad);
+        let layout = get_layout(model).context("Failed to load layout")?;
+        (layout, devices, i2c_address)
+    } else {
+        let layout = get_layout(&args.model).context("Failed to load layout")?;
+
+        // Detect devices with retries
+        let devices = detect_devices(
+            layout.try_times(),
+            Duration::from_millis(layout.try_sleep_ms()),
+        )
+        .context("Failed to detect devices")?;
+        (layout, devices, None)
+    };
 
-    // Detect devices with retries
-    let devices = detect_devices(
-        layout.try_times(),
-        Duration::from_millis(layout.try_sleep_ms()),
-    )
-    .context("Failed to detect devices")?;
+    // Apply key overrides before the virtual keyboard registers keys
+    let layout = config
+        .apply(layout)
+        .context("Failed to apply configuration")?;
+    if !config.remap.is_empty() {
+        info!(
+            "Applied {} key remap(s) from configuration",
+            config.remap.len()
+        );
+    }
+    if let Some(name) = &config.locale.name {
+        info!("Using keypad output for locale {}", name);
+    }
+    if config.transform != LayoutTransform::None {
+        info!("Layout transform: {:?}", config.transform);
+    }
+    if config.activation != ActivationGesture::Touch {
+        info!("Activation gesture: {:?}", config.activation);
+    }
+    if let Some(repeat) = config.repeat {
+        info!(
+            "Key repeat: {}ms delay, {} per second",
+            repeat.delay_ms, repeat.rate
+        );
+    }
+    if config.press != PressMode::Touch {
+        info!("Press mode: {:?}", config.press);
+    }
+    if let Some(tap) = config.tap {
+        info!(
+            "Tap mode: keys need a lift within {}ms after moving at most {}",
+            tap.max_duration_ms, tap.max_distance
+        );
+    }
 
     info!(
         "Found touchpad: {} at {}",
@@ -70,7 +159,10 @@ fn run_driver(args: RunArgs) -> Result<()> {
             keyboard.name, keyboard.event_path
         );
     }
-    info!("Using I2C address: 0x{:02x}", devices.i2c_address);
+    let i2c_address = auto_i2c_address
+        .or(layout.i2c_address())
+        .unwrap_or(devices.i2c_address);
+    info!("Using I2C address: 0x{:02x}", i2c_address);
 
     // Initialize touchpad reader
     let touchpad =
@@ -82,6 +174,41 @@ fn run_driver(args: RunArgs) -> Result<()> {
         bounds.min_x, bounds.max_x, bounds.min_y, bounds.max_y
     );
 
+    // Passthrough needs tap mode so that drags never type
+    let tap = match config.tap {
+        None if config.passthrough => Some(TapSettings::default()),
+        tap => tap,
+    };
+    let virtual_pointer = if config.passthrough {
+        match VirtualTouchpad::new(&touchpad) {
+            Ok(pointer) => {
+                info!("Pointer passthrough enabled");
+                Some(pointer)
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to create virtual touchpad, pointer passthrough disabled: {}",
+                    e
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let passthrough = virtual_pointer
+        .as_ref()
+        .map(|_| Passthrough::new(tap.unwrap_or_default()));
+    // Tap mode never holds keys, so there is nothing to slide
+    let slides = match (config.press, tap) {
+        (PressMode::Slide, None) => Some(SlideTracker::new()),
+        (PressMode::Slide, Some(_)) => {
+            warn!("Slide press mode has no effect in tap mode");
+            None
+        }
+        _ => None,
+    };
+
     let virtual_keys = layout.all_keys();
 
     // Initialize virtual keyboard
@@ -89,19 +216,55 @@ fn run_driver(args: RunArgs) -> Result<()> {
         VirtualKeyboard::new(&virtual_keys).context("Failed to create virtual keyboard")?;
 
     // Initialize LED controller (optional - warn and continue on failure)
-    let led = try_create_led_controller(devices.touchpad.i2c_bus, devices.i2c_address);
+    let led = try_create_led_controller(devices.touchpad.i2c_bus, i2c_address);
     let numlock_was_on =
         read_numlock_state(devices.keyboard.as_ref().map(|kb| kb.event_path.as_str()));
 
+    let mut state = NumpadState::new();
+    state.palm_rejection = layout.palm_rejection().for_axes(&touchpad.contact_axes());
+    debug!("Palm rejection: {:?}", state.palm_rejection);
+
+    let pressure_threshold = config
+        .pressure_threshold
+        .or(layout.pressure_threshold())
+        .filter(|&threshold| threshold > 0.0);
+    let pressure = match (pressure_threshold, touchpad.pressure_range()) {
+        (Some(threshold), Some(range)) => {
+            let gate = PressureGate::new(threshold, range);
+            info!(
+                "Pressure threshold: {} ({} of {}-{})",
+                threshold,
+                gate.raw_threshold(),
+                range.min,
+                range.max
+            );
+            Some(gate)
+        }
+        (Some(_), None) => {
+            warn!("Touchpad reports no pressure, pressure threshold ignored");
+            None
+        }
+        (None, _) => None,
+    };
+
     // Create driver context
     let mut ctx = DriverContext {
-        state: NumpadState::new(),
+        state,
         virtual_kb,
         led,
+        flash: config.flash.map(LedFlash::new),
         touchpad,
         layout: layout.as_ref(),
         bounds,
-        pending_finger_event: None,
+        tap,
+        press: config.press,
+        slides,
+        pressure,
+        passthrough,
+        virtual_pointer,
+        key_repeat: config.repeat.map(KeyRepeat::new),
+        activation: Activation::new(config.activation),
+        sliders: SliderTracker::new(),
         numlock_was_on,
         numlock_toggled_by_driver: false,
     };
@@ -111,6 +274,23 @@ fn run_driver(args: RunArgs) -> Result<()> {
 
     // Main event loop
     while !SHUTDOWN_REQUESTED.load(Ordering::SeqCst) {
+        // Wake up for the next key repeat or the end of an LED flash even if
+        // the touchpad stays quiet
+        let due = [
+            ctx.key_repeat.as_ref().and_then(KeyRepeat::next_due),
+            ctx.flash.as_ref().and_then(LedFlash::next_due),
+        ]
+        .into_iter()
+        .flatten()
+        .min();
+        if let Some(due) = due {
+            let timeout = due.saturating_duration_since(Instant::now());
+            if !ctx.touchpad.poll(timeout).unwrap_or(false) {
+                run_timers(&mut ctx);
+                continue;
+            }
+        }
+
         match ctx.touchpad.fetch_events() {
             Ok(events) => {
                 for event in events {
@@ -128,6 +308,7 @@ fn run_driver(args: RunArgs) -> Result<()> {
                 std::thread::sleep(Duration::from_millis(100));
             }
         }
+        run_timers(&mut ctx);
     }
 
     info!("Shutdown requested, cleaning up driver state");
@@ -136,35 +317,127 @@ fn run_driver(args: RunArgs) -> Result<()> {
     Ok(())
 }
 
+/// Resolve the `auto` model from the DMI product name and touchpad ID
+fn auto_select_model(touchpad: &InputDeviceInfo) -> (&'static str, Option<u8>) {
+    let product_name = read_product_name();
+    let touchpad_id = touchpad
+        .id
+        .map(|id| id.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    match select_hardware(product_name.as_deref(), touchpad.id) {
+        Some(selected) => {
+            info!(
+                "Auto-selected layout {} (I2C address 0x{:02x}) for product {:?}, touchpad {} (matched DMI: {}, touchpad ID: {})",
+                selected.entry.layout,
+                selected.entry.i2c_address,
+                product_name.as_deref().unwrap_or("unknown"),
+                touchpad_id,
+                selected.matched_dmi,
+                selected.matched_touchpad
+            );
+            (selected.entry.layout, Some(selected.entry.i2c_address))
+        }
+        None => {
+            warn!(
+                "No known layout for product {:?}, touchpad {}; falling back to {}",
+                product_name.as_deref().unwrap_or("unknown"),
+                touchpad_id,
+                AUTO_FALLBACK_MODEL
+            );
+            (AUTO_FALLBACK_MODEL, None)
+        }
+    }
+}
+
 fn process_event(event: &evdev::InputEvent, ctx: &mut DriverContext) -> Result<()> {
-    use evdev::EventType;
-
-    match event.event_type() {
-        EventType::ABSOLUTE => {
-            let code = AbsoluteAxisCode(event.code());
-            match code {
-                AbsoluteAxisCode::ABS_MT_POSITION_X | AbsoluteAxisCode::ABS_X => {
-                    ctx.state
-                        .update_x(event.value(), ctx.bounds.min_x, ctx.bounds.max_x);
-                }
-                AbsoluteAxisCode::ABS_MT_POSITION_Y | AbsoluteAxisCode::ABS_Y => {
-                    ctx.state
-                        .update_y(event.value(), ctx.bounds.min_y, ctx.bounds.max_y);
-                }
-                _ => {}
-            }
+    let changes = ctx.state.handle_event(event, &ctx.bounds);
+    for change in &changes {
+        match change {
+            ContactChange::Down(contact) => handle_touch_down(contact, ctx)?,
+            ContactChange::Up(contact) => handle_touch_up(contact, ctx)?,
         }
-        EventType::KEY => {
-            let key = KeyCode(event.code());
-            if key == KeyCode::BTN_TOOL_FINGER {
-                ctx.pending_finger_event = Some(event.value());
-            }
+    }
+    if event.event_type() == EventType::SYNCHRONIZATION
+        && SynchronizationCode(event.code()) == SynchronizationCode::SYN_REPORT
+    {
+        handle_activation(&changes, ctx)?;
+        if ctx.state.enabled {
+            handle_sliders(&changes, ctx)?;
+            handle_pressure(&changes, ctx)?;
+            handle_slides(&changes, ctx)?;
+        }
+    }
+
+    if let (Some(passthrough), Some(pointer)) = (&mut ctx.passthrough, &mut ctx.virtual_pointer) {
+        let forwarded = passthrough.process(event, &changes, &ctx.state, ctx.layout);
+        // Without the grab the real touchpad already drives the pointer
+        if ctx.state.enabled {
+            pointer.emit(&forwarded)?;
+        }
+    }
+    Ok(())
+}
+
+fn handle_touch_down(contact: &Contact, ctx: &mut DriverContext) -> Result<()> {
+    let position = contact.position;
+    debug!(
+        "Finger {} down in slot {} at x={:.2}, y={:.2}",
+        contact.tracking_id, contact.slot, position.x, position.y
+    );
+
+    let zone = ctx
+        .layout
+        .zone_at_position(position.x, position.y)
+        .map(|zone| zone.action.clone());
+
+    match zone {
+        Some(ZoneAction::Toggle) => {
+            // Left to the activation gesture, which may need more than a touch
         }
-        EventType::SYNCHRONIZATION
-            if SynchronizationCode(event.code()) == SynchronizationCode::SYN_REPORT =>
+        Some(ZoneAction::Calculator) if !ctx.state.enabled => {
+            // Launch calculator
+            ctx.virtual_kb.click_key(KeyCode::KEY_CALC)?;
+            debug!("Calculator key sent");
+        }
+        Some(ZoneAction::Calculator | ZoneAction::Brightness) if ctx.state.enabled => {
+            cycle_brightness(ctx);
+        }
+        Some(ZoneAction::LayerSwitch) if ctx.state.enabled => {
+            ctx.state.cycle_layer(ctx.layout.layer_count());
+            info!(
+                "Numpad layer: {}",
+                ctx.layout.layer_name(ctx.state.active_layer)
+            );
+        }
+        Some(ZoneAction::Action(action)) if ctx.state.enabled => {
+            debug!("Zone action: {:?}", action);
+            perform_action(ctx, contact.slot, action)?;
+        }
+        None if ctx
+            .layout
+            .slider_at_position(position.x, position.y)
+            .is_some() =>
         {
-            if let Some(value) = ctx.pending_finger_event.take() {
-                handle_finger_event(value, ctx)?;
+            // Left to the slider, which follows the finger frame by frame
+        }
+        None if ctx.state.enabled && presses_on_touch(ctx) && ctx.pressure.is_some() => {
+            // Left to the pressure gate, which presses once the finger does
+        }
+        None if ctx.state.enabled && presses_on_touch(ctx) => {
+            // Numpad key press
+            if let Some(action) =
+                ctx.layout
+                    .action_at_layer_position(ctx.state.active_layer, position.x, position.y)
+            {
+                debug!(
+                    "Key action: {:?} at x={:.2}, y={:.2}",
+                    action, position.x, position.y
+                );
+
+                perform_action(ctx, contact.slot, action)?;
+            } else {
+                flash_led(ctx, FlashKind::DeadZone);
             }
         }
         _ => {}
@@ -173,89 +446,300 @@ fn process_event(event: &evdev::InputEvent, ctx: &mut DriverContext) -> Result<(
     Ok(())
 }
 
-fn handle_finger_event(value: i32, ctx: &mut DriverContext) -> Result<()> {
-    println!(
-        "X={:.2}, Y={:.2}",
-        ctx.state.current_position.x, ctx.state.current_position.y
-    );
-    if value == 0 {
-        // Finger up - release any pressed key
-        debug!(
-            "Finger up at x={:.2}, y={:.2}",
-            ctx.state.current_position.x, ctx.state.current_position.y
-        );
-
-        release_pressed_key(ctx)?;
-    } else if value == 1 && ctx.state.pressed_key.is_none() {
-        // Finger down - handle corner detection or key press
-        debug!(
-            "Finger down at x={:.2}, y={:.2}",
-            ctx.state.current_position.x, ctx.state.current_position.y
-        );
+/// Whether keys are pressed while the finger is down rather than when it lifts
+fn presses_on_touch(ctx: &DriverContext) -> bool {
+    ctx.tap.is_none() && ctx.press != PressMode::Release
+}
 
-        let position = ctx.state.current_position;
-        let corner = corner_at_position(ctx.layout, position);
-
-        match corner {
-            Corner::TopRight => {
-                // Toggle numpad
-                if !ctx.state.enabled {
-                    enable_numpad(ctx)?;
-                    ctx.state.enabled = true;
-                    info!("Numpad enabled");
-                } else {
-                    disable_numpad(ctx)?;
-                    ctx.state.enabled = false;
-                    info!("Numpad disabled");
-                }
+/// Press the keys of fingers that pushed past the pressure threshold, under
+/// where they are now
+fn handle_pressure(changes: &[ContactChange], ctx: &mut DriverContext) -> Result<()> {
+    let Some(ref mut gate) = ctx.pressure else {
+        return Ok(());
+    };
+    let pressed = gate.process(changes, &ctx.state.contacts, ctx.layout);
+    if !presses_on_touch(ctx) {
+        // Checked when the finger lifts instead
+        return Ok(());
+    }
+    for slot in pressed {
+        let Some(position) = ctx.state.contact(slot).map(|contact| contact.position) else {
+            continue;
+        };
+        match ctx
+            .layout
+            .action_at_layer_position(ctx.state.active_layer, position.x, position.y)
+        {
+            Some(action) => {
+                debug!(
+                    "Pressed action: {:?} at x={:.2}, y={:.2}",
+                    action, position.x, position.y
+                );
+                perform_action(ctx, slot, action)?;
             }
-            Corner::TopLeft => {
-                if ctx.state.enabled {
-                    // Cycle brightness
-                    ctx.state.cycle_brightness();
-                    if let Some(ref mut led_ctrl) = ctx.led {
-                        if let Err(e) = led_ctrl.set_brightness(ctx.state.brightness) {
-                            warn!("Failed to change brightness: {}", e);
-                        }
+            None => flash_led(ctx, FlashKind::DeadZone),
+        }
+    }
+    Ok(())
+}
+
+/// Turn the numpad on or off once a finger completes the activation gesture
+fn handle_activation(changes: &[ContactChange], ctx: &mut DriverContext) -> Result<()> {
+    let layout = ctx.layout;
+    let in_zone = |position: TouchPosition| {
+        layout
+            .zone_at_position(position.x, position.y)
+            .is_some_and(|zone| zone.action == ZoneAction::Toggle)
+    };
+    let Some(finger) = ctx
+        .activation
+        .process(changes, &ctx.state.contacts, in_zone)
+    else {
+        return Ok(());
+    };
+
+    debug!("Finger {} completed the activation gesture", finger);
+    if !ctx.state.enabled {
+        enable_numpad(ctx)?;
+        ctx.state.enabled = true;
+        info!("Numpad enabled");
+    } else {
+        disable_numpad(ctx)?;
+        ctx.state.enabled = false;
+        info!("Numpad disabled");
+    }
+    Ok(())
+}
+
+/// Apply the brightness changes and key steps of fingers dragging sliders
+fn handle_sliders(changes: &[ContactChange], ctx: &mut DriverContext) -> Result<()> {
+    for step in ctx
+        .sliders
+        .process(changes, &ctx.state.contacts, ctx.layout)
+    {
+        match step {
+            SliderStep::Brightness(brightness) => {
+                ctx.state.brightness = brightness;
+                if let Some(ref mut led_ctrl) = ctx.led {
+                    if let Err(e) = led_ctrl.set_brightness(brightness) {
+                        warn!("Failed to change brightness: {}", e);
                     }
-                    debug!("Brightness changed to {:?}", ctx.state.brightness);
-                } else {
-                    // Launch calculator
-                    ctx.virtual_kb.click_key(KeyCode::KEY_CALC)?;
-                    debug!("Calculator key sent");
                 }
+                debug!("Brightness slid to {:?}", brightness);
             }
-            Corner::None if ctx.state.enabled => {
-                // Numpad key press
-                if let Some(key) = ctx.layout.key_at_position(position.x, position.y) {
-                    debug!(
-                        "Key press: {:?} at x={:.2}, y={:.2}",
-                        key, position.x, position.y
-                    );
-
-                    ctx.virtual_kb.press_key(key)?;
-                    ctx.state.pressed_key = Some(key);
-                }
+            SliderStep::Key(key) => {
+                debug!("Slider key: {:?}", key);
+                ctx.virtual_kb.click_key(key)?;
             }
-            _ => {}
         }
     }
+    Ok(())
+}
 
+/// In slide mode, move the keys of fingers that slid onto another key
+fn handle_slides(changes: &[ContactChange], ctx: &mut DriverContext) -> Result<()> {
+    let Some(ref mut slides) = ctx.slides else {
+        return Ok(());
+    };
+    let layer = ctx.state.active_layer;
+    for slide in slides.process(changes, &ctx.state.contacts, ctx.layout, layer) {
+        let tracking_id = ctx.state.contact(slide.slot).map(|c| c.tracking_id);
+        if let (Some(gate), Some(tracking_id)) = (&ctx.pressure, tracking_id) {
+            if !gate.has_pressed(tracking_id) {
+                // Pressed where it ends up once it pushes hard enough
+                continue;
+            }
+        }
+        debug!("Slot {} slid onto {:?}", slide.slot, slide.action);
+        if let Some(key) = ctx
+            .state
+            .contact_mut(slide.slot)
+            .and_then(|contact| contact.pressed_key.take())
+        {
+            ctx.virtual_kb.release_key(key)?;
+        }
+        if let Some(ref mut key_repeat) = ctx.key_repeat {
+            key_repeat.stop(slide.slot);
+        }
+        perform_action(ctx, slide.slot, slide.action)?;
+    }
     Ok(())
 }
 
-fn corner_at_position(layout: &dyn NumpadLayout, position: TouchPosition) -> Corner {
-    if layout.is_toggle_position(position.x, position.y) {
-        Corner::TopRight
-    } else if position.corner() == Corner::TopLeft {
-        Corner::TopLeft
-    } else {
-        Corner::None
+/// Release the key held by a lifted finger; other fingers keep theirs
+fn handle_touch_up(contact: &Contact, ctx: &mut DriverContext) -> Result<()> {
+    debug!(
+        "Finger {} up in slot {} at x={:.2}, y={:.2}",
+        contact.tracking_id, contact.slot, contact.position.x, contact.position.y
+    );
+
+    if let Some(key) = contact.pressed_key {
+        debug!("Releasing key: {:?}", key);
+        ctx.virtual_kb.release_key(key)?;
+    }
+    if let Some(ref mut key_repeat) = ctx.key_repeat {
+        key_repeat.stop(contact.slot);
+    }
+    if ctx.tap.is_some() || ctx.press == PressMode::Release {
+        handle_lift(contact, ctx)?;
+    }
+    Ok(())
+}
+
+/// In tap or release mode, send the key of a lifted finger: where it landed
+/// if it was a tap, or where it lifted in release mode
+fn handle_lift(contact: &Contact, ctx: &mut DriverContext) -> Result<()> {
+    if !ctx.state.enabled {
+        return Ok(());
+    }
+    let Some(lift) = ctx
+        .press
+        .lift(contact, ctx.tap, ctx.layout, ctx.state.active_layer)
+    else {
+        return Ok(());
+    };
+    if ctx
+        .passthrough
+        .as_ref()
+        .is_some_and(Passthrough::is_forwarding)
+    {
+        debug!(
+            "Finger {} was forwarded to the pointer",
+            contact.tracking_id
+        );
+        return Ok(());
+    }
+    if ctx
+        .pressure
+        .as_ref()
+        .is_some_and(|gate| !gate.has_pressed(contact.tracking_id))
+    {
+        debug!("Finger {} never pressed hard enough", contact.tracking_id);
+        return Ok(());
+    }
+
+    let position = ctx.press.lift_position(contact);
+    match lift {
+        Lift::Send(Action::Key(key)) => {
+            debug!(
+                "Lifted key: {:?} at x={:.2}, y={:.2}",
+                key, position.x, position.y
+            );
+            ctx.virtual_kb.click_key(key)?;
+            flash_led(ctx, FlashKind::Key);
+        }
+        Lift::Send(action) => {
+            debug!(
+                "Lifted action: {:?} at x={:.2}, y={:.2}",
+                action, position.x, position.y
+            );
+            perform_action(ctx, contact.slot, action)?;
+        }
+        Lift::Cancelled => {
+            debug!(
+                "Finger {} lifted off the keys, press cancelled",
+                contact.tracking_id
+            );
+            flash_led(ctx, FlashKind::DeadZone);
+        }
+    }
+    Ok(())
+}
+
+fn cycle_brightness(ctx: &mut DriverContext) {
+    ctx.state.cycle_brightness();
+    if let Some(ref mut led_ctrl) = ctx.led {
+        if let Err(e) = led_ctrl.set_brightness(ctx.state.brightness) {
+            warn!("Failed to change brightness: {}", e);
+        }
+    }
+    debug!("Brightness changed to {:?}", ctx.state.brightness);
+}
+
+/// Perform an action for the finger in `slot`, flashing the backlight when
+/// it typed on the virtual keyboard
+fn perform_action(ctx: &mut DriverContext, slot: usize, action: Action) -> Result<()> {
+    match action {
+        Action::Key(key) if ctx.key_repeat.is_some() => {
+            // Clicked now and again while the finger stays, so the session's
+            // own repeat never sees a held key
+            ctx.virtual_kb.click_key(key)?;
+            if let Some(ref mut key_repeat) = ctx.key_repeat {
+                key_repeat.start(slot, key, Instant::now());
+            }
+            flash_led(ctx, FlashKind::Key);
+        }
+        Action::Key(key) => {
+            // Held until the finger lifts; a key another finger holds stays with it
+            if ctx.state.is_key_held(key) {
+                debug!("Key {:?} is already held by another finger", key);
+                return Ok(());
+            }
+            let Some(contact) = ctx.state.contact_mut(slot) else {
+                return Ok(());
+            };
+            ctx.virtual_kb.press_key(key)?;
+            contact.pressed_key = Some(key);
+            flash_led(ctx, FlashKind::Key);
+        }
+        Action::Combo(_) | Action::Text(_) => {
+            ctx.virtual_kb.type_strokes(&action.strokes())?;
+            flash_led(ctx, FlashKind::Key);
+        }
+        Action::Command(command) => spawn_command(&command),
+    }
+    Ok(())
+}
+
+/// Pulse the backlight as feedback, if flashing is on and the LED is reachable
+fn flash_led(ctx: &mut DriverContext, kind: FlashKind) {
+    if let (Some(flash), Some(led_ctrl)) = (&mut ctx.flash, &mut ctx.led) {
+        flash.flash(kind, led_ctrl, ctx.state.brightness, Instant::now());
+    }
+}
+
+/// Repeat held keys and end LED flashes that are due
+fn run_timers(ctx: &mut DriverContext) {
+    repeat_keys(ctx);
+    if let (Some(flash), Some(led_ctrl)) = (&mut ctx.flash, &mut ctx.led) {
+        flash.restore(led_ctrl, ctx.state.brightness, Instant::now());
+    }
+}
+
+/// Click the keys whose repeat is due
+fn repeat_keys(ctx: &mut DriverContext) {
+    let Some(ref mut key_repeat) = ctx.key_repeat else {
+        return;
+    };
+    for key in key_repeat.due(Instant::now()) {
+        if let Err(e) = ctx.virtual_kb.click_key(key) {
+            warn!("Failed to repeat key {:?}: {}", key, e);
+        }
+    }
+}
+
+/// Run a shell command without waiting for it to finish
+fn spawn_command(command: &str) {
+    match std::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .spawn()
+    {
+        Ok(mut child) => {
+            // Reap the child in the background so it does not linger as a zombie
+            std::thread::spawn(move || {
+                let _ = child.wait();
+            });
+        }
+        Err(e) => warn!("Failed to run command {:?}: {}", command, e),
     }
 }
 
 fn enable_numpad(ctx: &mut DriverContext) -> Result<()> {
     ctx.touchpad.grab()?;
+    if let Some(ref mut passthrough) = ctx.passthrough {
+        passthrough.reset();
+    }
     if !ctx.numlock_was_on.unwrap_or(false) && !ctx.numlock_toggled_by_driver {
         ctx.virtual_kb.click_numlock()?;
         ctx.numlock_toggled_by_driver = true;
@@ -269,8 +753,22 @@ fn enable_numpad(ctx: &mut DriverContext) -> Result<()> {
 }
 
 fn disable_numpad(ctx: &mut DriverContext) -> Result<()> {
-    release_pressed_key(ctx)?;
+    release_pressed_keys(ctx)?;
+    ctx.state.active_layer = 0;
     ctx.touchpad.ungrab()?;
+    ctx.sliders.clear();
+    if let Some(ref mut gate) = ctx.pressure {
+        gate.clear();
+    }
+    if let Some(ref mut flash) = ctx.flash {
+        flash.cancel();
+    }
+    if let Some(ref mut slides) = ctx.slides {
+        slides.clear();
+    }
+    if let Some(ref mut passthrough) = ctx.passthrough {
+        passthrough.reset();
+    }
     if ctx.numlock_toggled_by_driver {
         ctx.virtual_kb.click_numlock()?;
         ctx.numlock_toggled_by_driver = false;
@@ -283,8 +781,12 @@ fn disable_numpad(ctx: &mut DriverContext) -> Result<()> {
     Ok(())
 }
 
-fn release_pressed_key(ctx: &mut DriverContext) -> Result<()> {
-    if let Some(key) = ctx.state.pressed_key.take() {
+/// Release every key held by a finger still on the pad
+fn release_pressed_keys(ctx: &mut DriverContext) -> Result<()> {
+    if let Some(ref mut key_repeat) = ctx.key_repeat {
+        key_repeat.clear();
+    }
+    for key in ctx.state.take_pressed_keys() {
         debug!("Releasing key: {:?}", key);
         ctx.virtual_kb.release_key(key)?;
     }
@@ -344,22 +846,20 @@ fn notify_systemd(state: &[(&str, &str)]) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use layouts::zone_action_at;
 
     #[test]
     fn g634jy_corner_detection_respects_layout_toggle_dead_zone() {
         let layout = layouts::G634jyLayout::new();
 
+        assert_eq!(zone_action_at(&layout, 0.32, 0.40), None);
         assert_eq!(
-            corner_at_position(&layout, TouchPosition { x: 0.32, y: 0.40 }),
-            Corner::None
-        );
-        assert_eq!(
-            corner_at_position(&layout, TouchPosition { x: 0.90, y: 0.20 }),
-            Corner::TopRight
+            zone_action_at(&layout, 0.90, 0.20),
+            Some(ZoneAction::Toggle)
         );
         assert_eq!(
-            corner_at_position(&layout, TouchPosition { x: 0.05, y: 0.05 }),
-            Corner::TopLeft
+            zone_action_at(&layout, 0.05, 0.05),
+            Some(ZoneAction::Calculator)
         );
     }
 }
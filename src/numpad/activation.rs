@@ -0,0 +1,231 @@
+use super::{Contact, ContactChange, TouchPosition};
+use serde::Deserialize;
+use std::time::Duration;
+
+/// How a finger in the toggle zone turns the numpad on or off
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(tag = "gesture", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum ActivationGesture {
+    /// Any finger landing in the zone
+    #[default]
+    Touch,
+    /// A finger held in the zone for `hold_ms`
+    LongPress {
+        #[serde(default = "default_hold_ms")]
+        hold_ms: u64,
+    },
+    /// Two short taps in the zone, the second landing within `window_ms` of
+    /// the first lifting
+    DoubleTap {
+        #[serde(default = "default_window_ms")]
+        window_ms: u64,
+    },
+    /// A finger landing in the zone and sliding at least `distance` towards
+    /// the middle of the pad
+    SwipeIn {
+        #[serde(default = "default_distance")]
+        distance: f64,
+    },
+}
+
+fn default_hold_ms() -> u64 {
+    500
+}
+
+fn default_window_ms() -> u64 {
+    300
+}
+
+fn default_distance() -> f64 {
+    0.15
+}
+
+impl ActivationGesture {
+    pub fn check(&self) -> Result<(), String> {
+        match self {
+            ActivationGesture::SwipeIn { distance } if !(0.0..=1.0).contains(distance) => Err(
+                format!("swipe-in distance must be in 0.0..=1.0, got {}", distance),
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Recognizes the activation gesture from the contacts of each frame
+#[derive(Debug)]
+pub struct Activation {
+    gesture: ActivationGesture,
+    /// Fingers on the pad that already toggled, so each toggles once
+    fired: Vec<i32>,
+    /// The last finger to lift after a short tap in the zone
+    last_tap: Option<Contact>,
+}
+
+impl Activation {
+    pub fn new(gesture: ActivationGesture) -> Self {
+        Self {
+            gesture,
+            fired: Vec::new(),
+            last_tap: None,
+        }
+    }
+
+    /// Feed a completed frame: the changes it produced and the fingers still
+    /// on the pad. Returns the tracking ID of the finger that completed the
+    /// gesture, if one did
+    pub fn process(
+        &mut self,
+        changes: &[ContactChange],
+        contacts: &[Contact],
+        in_zone: impl Fn(TouchPosition) -> bool,
+    ) -> Option<i32> {
+        let mut completed = None;
+        for change in changes {
+            if let ContactChange::Up(contact) = change {
+                if self.lifted(contact, &in_zone) {
+                    completed = Some(contact.tracking_id);
+                }
+                self.fired.retain(|&id| id != contact.tracking_id);
+            }
+        }
+        for contact in contacts.iter().filter(|c| !c.is_rejected()) {
+            if !self.fired.contains(&contact.tracking_id) && self.held(contact, &in_zone) {
+                self.fired.push(contact.tracking_id);
+                completed = Some(contact.tracking_id);
+            }
+        }
+        completed
+    }
+
+    /// Whether a finger still on the pad completes the gesture
+    fn held(&self, contact: &Contact, in_zone: impl Fn(TouchPosition) -> bool) -> bool {
+        if !in_zone(contact.start) {
+            return false;
+        }
+        match self.gesture {
+            ActivationGesture::Touch => true,
+            ActivationGesture::LongPress { hold_ms } => {
+                in_zone(contact.position) && contact.duration() >= Duration::from_millis(hold_ms)
+            }
+            ActivationGesture::DoubleTap { .. } => false,
+            ActivationGesture::SwipeIn { distance } => {
+                let (start, position) = (contact.start, contact.position);
+                let from_middle = |p: TouchPosition| (p.x - 0.5).hypot(p.y - 0.5);
+                (position.x - start.x).hypot(position.y - start.y) >= distance
+                    && from_middle(position) < from_middle(start)
+            }
+        }
+    }
+
+    /// Whether a lifting finger completes the gesture
+    fn lifted(&mut self, contact: &Contact, in_zone: impl Fn(TouchPosition) -> bool) -> bool {
+        let window_ms = match self.gesture {
+            ActivationGesture::DoubleTap { window_ms } => window_ms,
+            // Touchpads may go quiet under a resting finger, so the hold can
+            // first show when it lifts
+            ActivationGesture::LongPress { .. } => {
+                return !self.fired.contains(&contact.tracking_id) && self.held(contact, in_zone);
+            }
+            _ => return false,
+        };
+        let window = Duration::from_millis(window_ms);
+        let tapped =
+            in_zone(contact.start) && in_zone(contact.position) && contact.duration() <= window;
+        if !tapped {
+            self.last_tap = None;
+            return false;
+        }
+
+        let first = self.last_tap.take();
+        let completed = first.is_some_and(|first| {
+            contact
+                .landed_at
+                .duration_since(first.last_seen)
+                .is_ok_and(|gap| gap <= window)
+        });
+        if !completed {
+            self.last_tap = Some(contact.clone());
+        }
+        completed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::{zone_action_at, G634jyLayout, ZoneAction};
+    use crate::numpad::{NumpadState, Recording};
+    use evdev::EventType;
+    use std::time::UNIX_EPOCH;
+
+    /// Tracking ID and kernel time in ms of each toggle
+    fn toggles(gesture: ActivationGesture) -> Vec<(i32, u128)> {
+        let recording =
+            Recording::parse(include_str!("../../tests/fixtures/corner_gestures.evemu"));
+        let layout = G634jyLayout::new();
+        let in_zone =
+            |p: TouchPosition| zone_action_at(&layout, p.x, p.y) == Some(ZoneAction::Toggle);
+        let mut state = NumpadState::new();
+        let mut activation = Activation::new(gesture);
+
+        let mut toggles = Vec::new();
+        for event in &recording.events {
+            let changes = state.handle_event(event, &recording.bounds);
+            if event.event_type() != EventType::SYNCHRONIZATION {
+                continue;
+            }
+            if let Some(id) = activation.process(&changes, &state.contacts, in_zone) {
+                let time = event.timestamp().duration_since(UNIX_EPOCH).unwrap();
+                toggles.push((id, time.as_millis()));
+            }
+        }
+        toggles
+    }
+
+    #[test]
+    fn any_touch_toggles_including_brushes() {
+        assert_eq!(
+            toggles(ActivationGesture::Touch),
+            vec![
+                (501, 1000),
+                (502, 2000),
+                (503, 3200),
+                (504, 3440),
+                (505, 4000)
+            ]
+        );
+    }
+
+    #[test]
+    fn long_press_toggles_once_after_hold() {
+        assert_eq!(
+            toggles(ActivationGesture::LongPress { hold_ms: 500 }),
+            vec![(502, 2520)]
+        );
+    }
+
+    #[test]
+    fn double_tap_toggles_on_second_tap() {
+        assert_eq!(
+            toggles(ActivationGesture::DoubleTap { window_ms: 300 }),
+            vec![(504, 3510)]
+        );
+        // The taps are 160ms apart
+        assert!(toggles(ActivationGesture::DoubleTap { window_ms: 150 }).is_empty());
+    }
+
+    #[test]
+    fn swipe_in_toggles_once_the_finger_has_moved_inwards() {
+        assert_eq!(
+            toggles(ActivationGesture::SwipeIn { distance: 0.15 }),
+            vec![(505, 4080)]
+        );
+        assert_eq!(
+            toml::from_str::<ActivationGesture>("gesture = \"swipe-in\"").unwrap(),
+            ActivationGesture::SwipeIn { distance: 0.15 }
+        );
+        assert!(ActivationGesture::SwipeIn { distance: 2.0 }
+            .check()
+            .is_err());
+    }
+}
@@ -1,3 +1,23 @@
+mod activation;
+mod palm;
+mod passthrough;
+mod press;
+mod pressure;
+#[cfg(test)]
+mod recording;
+mod repeat;
+mod slider;
 mod state;
+mod tap;
 
-pub use state::{Corner, NumpadState, TouchPosition};
+pub use activation::{Activation, ActivationGesture};
+pub use palm::PalmRejection;
+pub use passthrough::Passthrough;
+pub use press::{Lift, PressMode, SlideTracker};
+pub use pressure::PressureGate;
+#[cfg(test)]
+pub use recording::Recording;
+pub use repeat::{KeyRepeat, RepeatSettings};
+pub use slider::{SliderStep, SliderTracker};
+pub use state::{Contact, ContactChange, NumpadState, TouchPosition};
+pub use tap::TapSettings;
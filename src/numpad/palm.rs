@@ -0,0 +1,106 @@
+use super::Contact;
+use crate::input::ContactAxes;
+use serde::Deserialize;
+
+/// `ABS_MT_TOOL_TYPE` value the kernel reports for a palm
+pub const MT_TOOL_PALM: i32 = 2;
+
+/// Contact size, pressure and tool type above which a touch counts as a palm
+/// or resting thumb; sizes and pressure are in raw device units
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PalmRejection {
+    /// Largest `ABS_MT_TOUCH_MAJOR` accepted
+    pub touch_major: Option<i32>,
+    /// Largest `ABS_MT_TOUCH_MINOR` accepted
+    pub touch_minor: Option<i32>,
+    /// Largest `ABS_MT_PRESSURE` accepted
+    pub pressure: Option<i32>,
+    /// Reject contacts the touchpad itself marks as `MT_TOOL_PALM`
+    #[serde(default = "default_tool_type")]
+    pub tool_type: bool,
+}
+
+fn default_tool_type() -> bool {
+    true
+}
+
+impl Default for PalmRejection {
+    fn default() -> Self {
+        Self {
+            touch_major: None,
+            touch_minor: None,
+            pressure: None,
+            tool_type: default_tool_type(),
+        }
+    }
+}
+
+impl PalmRejection {
+    /// Drop the checks for axes the touchpad does not report
+    pub fn for_axes(&self, axes: &ContactAxes) -> Self {
+        Self {
+            touch_major: self.touch_major.filter(|_| axes.touch_major),
+            touch_minor: self.touch_minor.filter(|_| axes.touch_minor),
+            pressure: self.pressure.filter(|_| axes.pressure),
+            tool_type: self.tool_type && axes.tool_type,
+        }
+    }
+
+    /// Why the contact counts as a palm, if it does
+    pub fn reason(&self, contact: &Contact) -> Option<String> {
+        if self.tool_type && contact.tool_type == Some(MT_TOOL_PALM) {
+            return Some("tool type is palm".to_string());
+        }
+        [
+            ("touch major", contact.touch_major, self.touch_major),
+            ("touch minor", contact.touch_minor, self.touch_minor),
+            ("pressure", contact.pressure, self.pressure),
+        ]
+        .into_iter()
+        .find_map(|(axis, value, limit)| match (value, limit) {
+            (Some(value), Some(limit)) if value > limit => {
+                Some(format!("{} {} exceeds {}", axis, value, limit))
+            }
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_checks_for_unsupported_axes() {
+        let rejection = PalmRejection {
+            touch_major: Some(1200),
+            touch_minor: Some(900),
+            pressure: Some(120),
+            tool_type: true,
+        };
+        let axes = ContactAxes {
+            touch_major: true,
+            pressure: true,
+            ..ContactAxes::default()
+        };
+
+        assert_eq!(
+            rejection.for_axes(&axes),
+            PalmRejection {
+                touch_major: Some(1200),
+                touch_minor: None,
+                pressure: Some(120),
+                tool_type: false,
+            }
+        );
+        assert_eq!(
+            toml::from_str::<PalmRejection>("pressure = 90").unwrap(),
+            PalmRejection {
+                pressure: Some(90),
+                ..PalmRejection::default()
+            }
+        );
+        assert!(toml::from_str::<PalmRejection>("size = 90").is_err());
+    }
+}
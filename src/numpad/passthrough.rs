@@ -0,0 +1,181 @@
+use super::{Contact, ContactChange, NumpadState, TapSettings};
+use crate::layouts::NumpadLayout;
+use evdev::{AbsoluteAxisCode, EventType, InputEvent, SynchronizationCode};
+use log::debug;
+
+/// Splits the grabbed touchpad's events between the numpad and a virtual
+/// pointer
+///
+/// Frames are held back while every finger on the pad could still be a tap on
+/// a key or control zone. Once a finger moves, stays down too long, lands off
+/// the numpad or is joined by another finger, the held frames and the rest of
+/// the touch are forwarded; a touch that ends as a tap is consumed.
+pub struct Passthrough {
+    tap: TapSettings,
+    /// Events of the frame being read
+    frame: Vec<InputEvent>,
+    /// Complete frames of a touch that may still be a tap
+    held: Vec<InputEvent>,
+    /// The current touch belongs to the pointer
+    forwarding: bool,
+    /// Latest `ABS_MT_SLOT`, and its value when the current touch began
+    slot: Option<i32>,
+    touch_slot: Option<i32>,
+}
+
+impl Passthrough {
+    pub fn new(tap: TapSettings) -> Self {
+        Self {
+            tap,
+            frame: Vec::new(),
+            held: Vec::new(),
+            forwarding: false,
+            slot: None,
+            touch_slot: None,
+        }
+    }
+
+    /// Whether the fingers on the pad are being forwarded to the pointer
+    pub fn is_forwarding(&self) -> bool {
+        self.forwarding
+    }
+
+    /// Drop held frames, e.g. when the numpad is turned on or off mid-touch
+    pub fn reset(&mut self) {
+        self.frame.clear();
+        self.held.clear();
+        self.forwarding = false;
+    }
+
+    /// Take an event already fed to `state`, with the changes it produced;
+    /// returns the events to replay on the virtual pointer
+    pub fn process(
+        &mut self,
+        event: &InputEvent,
+        changes: &[ContactChange],
+        state: &NumpadState,
+        layout: &dyn NumpadLayout,
+    ) -> Vec<InputEvent> {
+        if self.frame.is_empty() && self.held.is_empty() && !self.forwarding {
+            self.touch_slot = self.slot;
+        }
+        if event.event_type() == EventType::ABSOLUTE
+            && AbsoluteAxisCode(event.code()) == AbsoluteAxisCode::ABS_MT_SLOT
+        {
+            self.slot = Some(event.value());
+        }
+        self.frame.push(*event);
+        if event.event_type() != EventType::SYNCHRONIZATION
+            || SynchronizationCode(event.code()) != SynchronizationCode::SYN_REPORT
+        {
+            return Vec::new();
+        }
+
+        let frame = std::mem::take(&mut self.frame);
+        let mut forwarded = Vec::new();
+        if self.forwarding {
+            forwarded = frame;
+        } else if self.is_pointer(changes, state, layout) {
+            // The virtual pointer missed the slot changes of consumed taps
+            if let Some(slot) = self.touch_slot {
+                forwarded.push(InputEvent::new(
+                    EventType::ABSOLUTE.0,
+                    AbsoluteAxisCode::ABS_MT_SLOT.0,
+                    slot,
+                ));
+            }
+            forwarded.append(&mut self.held);
+            forwarded.extend(frame);
+            self.forwarding = true;
+        } else {
+            self.held.extend(frame);
+        }
+
+        if state.contacts.is_empty() {
+            if !self.held.is_empty() {
+                debug!(
+                    "Consumed {} touchpad events of a numpad tap",
+                    self.held.len()
+                );
+            }
+            self.reset();
+        }
+        forwarded
+    }
+
+    fn is_pointer(
+        &self,
+        changes: &[ContactChange],
+        state: &NumpadState,
+        layout: &dyn NumpadLayout,
+    ) -> bool {
+        let lifted = changes.iter().filter_map(|change| match change {
+            ContactChange::Up(contact) => Some(contact),
+            ContactChange::Down(_) => None,
+        });
+        let contacts: Vec<&Contact> = state.contacts.iter().chain(lifted).collect();
+
+        contacts.len() > 1
+            || contacts.iter().any(|contact| {
+                let start = contact.start;
+                // Slider drags are numpad input however far they move
+                if layout.slider_at_position(start.x, start.y).is_some() {
+                    return false;
+                }
+                let on_numpad = layout.zone_at_position(start.x, start.y).is_some()
+                    || layout
+                        .region_at_layer_position(state.active_layer, start.x, start.y)
+                        .is_some();
+                !on_numpad || !self.tap.is_tap(contact)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::G634jyLayout;
+    use crate::numpad::Recording;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn forward(recording: &Recording) -> Vec<InputEvent> {
+        let layout = G634jyLayout::new();
+        let mut state = NumpadState::new();
+        let mut passthrough = Passthrough::new(TapSettings::default());
+        let mut forwarded = Vec::new();
+        for event in &recording.events {
+            let changes = state.handle_event(event, &recording.bounds);
+            forwarded.extend(passthrough.process(event, &changes, &state, &layout));
+        }
+        forwarded
+    }
+
+    fn describe(events: &[InputEvent]) -> Vec<(u16, u16, i32)> {
+        events
+            .iter()
+            .map(|event| (event.event_type().0, event.code(), event.value()))
+            .collect()
+    }
+
+    #[test]
+    fn consumes_taps_and_forwards_drags_and_long_presses() {
+        let recording = Recording::parse(include_str!("../../tests/fixtures/tap_and_drag.evemu"));
+
+        // The tap on 7 ends before 2s; the drag and the resting finger after it
+        let after_tap: Vec<InputEvent> = recording
+            .events
+            .iter()
+            .copied()
+            .filter(|event| event.timestamp() >= UNIX_EPOCH + Duration::from_secs(2))
+            .collect();
+        assert_eq!(describe(&forward(&recording)), describe(&after_tap));
+    }
+
+    #[test]
+    fn forwards_multi_finger_touches() {
+        let recording =
+            Recording::parse(include_str!("../../tests/fixtures/two_finger_roll.evemu"));
+
+        assert_eq!(describe(&forward(&recording)), describe(&recording.events));
+    }
+}
@@ -0,0 +1,253 @@
+use super::{Contact, ContactChange, TapSettings, TouchPosition};
+use crate::layouts::{Action, NumpadLayout};
+use log::debug;
+use serde::Deserialize;
+
+/// When the key under a finger is chosen and sent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PressMode {
+    /// Pressed where the finger lands and released when it lifts
+    #[default]
+    Touch,
+    /// Sent where the finger lifts; lifting off every key cancels it
+    Release,
+    /// Pressed where the finger lands, then released and replaced by each
+    /// key the finger slides onto
+    Slide,
+}
+
+impl PressMode {
+    /// Where the key of a lifted finger is looked up
+    pub fn lift_position(&self, contact: &Contact) -> TouchPosition {
+        match self {
+            PressMode::Release => contact.position,
+            PressMode::Touch | PressMode::Slide => contact.start,
+        }
+    }
+
+    /// What `contact` sends as it lifts from `layer` in tap or release mode,
+    /// with `tap` set in tap mode; None when the finger started on a control,
+    /// was rejected as a palm or dragged in tap mode
+    pub fn lift(
+        &self,
+        contact: &Contact,
+        tap: Option<TapSettings>,
+        layout: &dyn NumpadLayout,
+        layer: usize,
+    ) -> Option<Lift> {
+        let start = contact.start;
+        if layout.zone_at_position(start.x, start.y).is_some()
+            || layout.slider_at_position(start.x, start.y).is_some()
+        {
+            return None;
+        }
+        if contact.is_rejected() {
+            debug!("Finger {} lifted as a palm", contact.tracking_id);
+            return None;
+        }
+        if tap.is_some_and(|tap| !tap.is_tap(contact)) {
+            debug!(
+                "Ignoring drag of finger {}: {}ms, moved {:.3}",
+                contact.tracking_id,
+                contact.duration().as_millis(),
+                contact.travel
+            );
+            return None;
+        }
+
+        let position = self.lift_position(contact);
+        if layout.zone_at_position(position.x, position.y).is_some() {
+            return Some(Lift::Cancelled);
+        }
+        match layout.action_at_layer_position(layer, position.x, position.y) {
+            Some(action) => Some(Lift::Send(action)),
+            None => Some(Lift::Cancelled),
+        }
+    }
+}
+
+/// Press made by a finger lifting in tap or release mode
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lift {
+    /// Lifted off the keys, which cancels the press
+    Cancelled,
+    /// Send the action under the finger
+    Send(Action),
+}
+
+/// Finger whose action changed as it slid onto another key
+#[derive(Debug, Clone, PartialEq)]
+pub struct Slide {
+    pub slot: usize,
+    pub action: Action,
+}
+
+/// Follows fingers that landed on a key in slide mode
+///
+/// A finger in the gap between keys or off the numpad keeps its key, so
+/// jitter along a key's edge does not retype it.
+#[derive(Debug, Default)]
+pub struct SlideTracker {
+    /// Tracking ID and current action of each followed finger
+    fingers: Vec<(i32, Action)>,
+}
+
+impl SlideTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.fingers.clear();
+    }
+
+    /// Feed a completed frame: the changes it produced and the fingers still
+    /// on the pad. Returns the fingers that slid onto another key
+    pub fn process(
+        &mut self,
+        changes: &[ContactChange],
+        contacts: &[Contact],
+        layout: &dyn NumpadLayout,
+        layer: usize,
+    ) -> Vec<Slide> {
+        for change in changes {
+            match change {
+                ContactChange::Down(contact) => {
+                    let start = contact.start;
+                    if layout.zone_at_position(start.x, start.y).is_some()
+                        || layout.slider_at_position(start.x, start.y).is_some()
+                    {
+                        continue;
+                    }
+                    if let Some(action) = layout.action_at_layer_position(layer, start.x, start.y) {
+                        self.fingers.push((contact.tracking_id, action));
+                    }
+                }
+                ContactChange::Up(contact) => {
+                    self.fingers.retain(|(id, _)| *id != contact.tracking_id);
+                }
+            }
+        }
+
+        let mut slides = Vec::new();
+        for (tracking_id, current) in &mut self.fingers {
+            let Some(contact) = contacts.iter().find(|c| c.tracking_id == *tracking_id) else {
+                continue;
+            };
+            let position = contact.position;
+            match layout.action_at_layer_position(layer, position.x, position.y) {
+                Some(action) if action != *current => {
+                    *current = action.clone();
+                    slides.push(Slide {
+                        slot: contact.slot,
+                        action,
+                    });
+                }
+                _ => {}
+            }
+        }
+        slides
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::G634jyLayout;
+    use crate::numpad::{NumpadState, Recording};
+    use evdev::{EventType, KeyCode};
+
+    fn recording() -> Recording {
+        Recording::parse(include_str!("../../tests/fixtures/slide_across_keys.evemu"))
+    }
+
+    #[test]
+    fn release_mode_uses_the_lift_position() {
+        let layout = G634jyLayout::new();
+        let lifted: Vec<(Option<KeyCode>, Option<KeyCode>)> = recording()
+            .replay(&mut NumpadState::new())
+            .into_iter()
+            .flatten()
+            .filter_map(|change| match change {
+                ContactChange::Up(contact) => Some(contact),
+                ContactChange::Down(_) => None,
+            })
+            .map(|contact| {
+                let key_at = |mode: PressMode| {
+                    let position = mode.lift_position(&contact);
+                    layout.key_at_position(position.x, position.y)
+                };
+                (key_at(PressMode::Touch), key_at(PressMode::Release))
+            })
+            .collect();
+
+        assert_eq!(
+            lifted,
+            vec![
+                (Some(KeyCode::KEY_KP4), Some(KeyCode::KEY_KP6)),
+                // Slid off the keys, so release mode cancels it
+                (Some(KeyCode::KEY_KP1), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn release_mode_skips_palms() {
+        let (recording, palm_rejection) = Recording::palm_on_enter();
+        let layout = G634jyLayout::new();
+        let mut state = NumpadState::new();
+        state.palm_rejection = palm_rejection;
+        let lifts: Vec<(i32, Option<Lift>)> = recording
+            .replay(&mut state)
+            .into_iter()
+            .flatten()
+            .filter_map(|change| match change {
+                ContactChange::Up(contact) => Some(contact),
+                ContactChange::Down(_) => None,
+            })
+            .map(|contact| {
+                let lift = PressMode::Release.lift(&contact, None, &layout, 0);
+                (contact.tracking_id, lift)
+            })
+            .collect();
+
+        assert_eq!(
+            lifts,
+            vec![
+                // The thumb lifts over the dot key once rejected as a palm
+                (303, None),
+                (301, Some(Lift::Send(Action::Key(KeyCode::KEY_KP5)))),
+            ]
+        );
+    }
+
+    #[test]
+    fn slide_mode_moves_the_key_across_hitboxes() {
+        let recording = recording();
+        let layout = G634jyLayout::new();
+        let mut state = NumpadState::new();
+        let mut slides = SlideTracker::new();
+
+        let mut actions = Vec::new();
+        for event in &recording.events {
+            let changes = state.handle_event(event, &recording.bounds);
+            if event.event_type() == EventType::SYNCHRONIZATION {
+                actions.extend(
+                    slides
+                        .process(&changes, &state.contacts, &layout, 0)
+                        .into_iter()
+                        .map(|slide| slide.action),
+                );
+            }
+        }
+
+        // The gap between 4 and 5, a second frame on 5 and sliding off 1
+        // change nothing
+        assert_eq!(
+            actions,
+            vec![Action::Key(KeyCode::KEY_KP5), Action::Key(KeyCode::KEY_KP6)]
+        );
+        assert!(slides.fingers.is_empty());
+    }
+}
@@ -0,0 +1,200 @@
+use super::state::normalize_axis;
+use super::{Contact, ContactChange};
+use crate::input::PressureRange;
+use crate::layouts::NumpadLayout;
+
+/// Holds back the keys of numpad fingers until they press hard enough, so a
+/// resting finger does not type
+#[derive(Debug)]
+pub struct PressureGate {
+    /// Pressure to reach, normalized over `range`
+    threshold: f64,
+    range: PressureRange,
+    /// Tracking IDs of fingers that landed on the keys, and whether each has
+    /// reached the threshold
+    fingers: Vec<(i32, bool)>,
+}
+
+impl PressureGate {
+    pub fn new(threshold: f64, range: PressureRange) -> Self {
+        Self {
+            threshold,
+            range,
+            fingers: Vec::new(),
+        }
+    }
+
+    pub fn check_threshold(threshold: f64) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err(format!(
+                "pressure_threshold must be in 0.0..=1.0, got {}",
+                threshold
+            ));
+        }
+        Ok(())
+    }
+
+    /// Raw pressure matching the threshold, for logging
+    pub fn raw_threshold(&self) -> i32 {
+        let span = (self.range.max - self.range.min) as f64;
+        self.range.min + (self.threshold * span).ceil() as i32
+    }
+
+    /// Whether the finger with `tracking_id` has pressed hard enough
+    pub fn has_pressed(&self, tracking_id: i32) -> bool {
+        self.fingers
+            .iter()
+            .any(|&(id, pressed)| id == tracking_id && pressed)
+    }
+
+    pub fn clear(&mut self) {
+        self.fingers.clear();
+    }
+
+    /// Feed a completed frame: the changes it produced and the fingers still
+    /// on the pad. Returns the slots of fingers that reached the threshold in
+    /// this frame, including those that land pressing hard enough
+    pub fn process(
+        &mut self,
+        changes: &[ContactChange],
+        contacts: &[Contact],
+        layout: &dyn NumpadLayout,
+    ) -> Vec<usize> {
+        for change in changes {
+            match change {
+                ContactChange::Down(contact) => {
+                    let start = contact.start;
+                    if layout.zone_at_position(start.x, start.y).is_none()
+                        && layout.slider_at_position(start.x, start.y).is_none()
+                    {
+                        self.fingers.push((contact.tracking_id, false));
+                    }
+                }
+                ContactChange::Up(contact) => {
+                    self.fingers.retain(|(id, _)| *id != contact.tracking_id);
+                }
+            }
+        }
+
+        let mut pressed = Vec::new();
+        for (tracking_id, reached) in self.fingers.iter_mut().filter(|(_, reached)| !reached) {
+            let Some(contact) = contacts.iter().find(|c| c.tracking_id == *tracking_id) else {
+                continue;
+            };
+            let pressure = contact
+                .pressure
+                .map(|value| normalize_axis(value, self.range.min, self.range.max));
+            if pressure.is_some_and(|pressure| pressure >= self.threshold) {
+                *reached = true;
+                pressed.push(contact.slot);
+            }
+        }
+        pressed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::TouchpadBounds;
+    use crate::layouts::G634jyLayout;
+    use crate::numpad::NumpadState;
+    use evdev::{AbsoluteAxisCode, EventType, InputEvent, KeyCode, SynchronizationCode};
+
+    const BOUNDS: TouchpadBounds = TouchpadBounds {
+        min_x: 0,
+        max_x: 1000,
+        min_y: 0,
+        max_y: 1000,
+    };
+
+    fn abs(axis: AbsoluteAxisCode, value: i32) -> InputEvent {
+        InputEvent::new(EventType::ABSOLUTE.0, axis.0, value)
+    }
+
+    fn syn() -> InputEvent {
+        InputEvent::new(
+            EventType::SYNCHRONIZATION.0,
+            SynchronizationCode::SYN_REPORT.0,
+            0,
+        )
+    }
+
+    /// Slots that reached the threshold in each frame
+    fn pressed_per_frame(frames: &[Vec<InputEvent>], gate: &mut PressureGate) -> Vec<Vec<usize>> {
+        let layout = G634jyLayout::new();
+        let mut state = NumpadState::new();
+        frames
+            .iter()
+            .map(|frame| {
+                let mut changes = Vec::new();
+                for event in frame.iter().chain([&syn()]) {
+                    changes.extend(state.handle_event(event, &BOUNDS));
+                }
+                gate.process(&changes, &state.contacts, &layout)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn resting_finger_registers_once_it_presses_harder() {
+        // 0.3 of 0..=255 is 77 raw
+        let mut gate = PressureGate::new(0.3, PressureRange { min: 0, max: 255 });
+        assert_eq!(gate.raw_threshold(), 77);
+
+        use AbsoluteAxisCode as Abs;
+        let frames = vec![
+            // A finger rests on 5, then presses through the threshold
+            vec![
+                abs(Abs::ABS_MT_SLOT, 0),
+                abs(Abs::ABS_MT_TRACKING_ID, 801),
+                abs(Abs::ABS_MT_POSITION_X, 320),
+                abs(Abs::ABS_MT_POSITION_Y, 400),
+                abs(Abs::ABS_MT_PRESSURE, 30),
+            ],
+            vec![abs(Abs::ABS_MT_PRESSURE, 60)],
+            // A second finger lands on 9 already pressing hard enough
+            vec![
+                abs(Abs::ABS_MT_PRESSURE, 90),
+                abs(Abs::ABS_MT_SLOT, 1),
+                abs(Abs::ABS_MT_TRACKING_ID, 802),
+                abs(Abs::ABS_MT_POSITION_X, 500),
+                abs(Abs::ABS_MT_POSITION_Y, 150),
+                abs(Abs::ABS_MT_PRESSURE, 200),
+            ],
+            // Easing off and pressing again does not register twice
+            vec![abs(Abs::ABS_MT_PRESSURE, 20)],
+            vec![abs(Abs::ABS_MT_PRESSURE, 210)],
+        ];
+
+        assert_eq!(
+            pressed_per_frame(&frames, &mut gate),
+            vec![vec![], vec![], vec![0, 1], vec![], vec![]]
+        );
+        assert!(gate.has_pressed(801) && gate.has_pressed(802));
+    }
+
+    #[test]
+    fn single_touch_pads_use_abs_pressure() {
+        let mut gate = PressureGate::new(0.5, PressureRange { min: 0, max: 100 });
+
+        use AbsoluteAxisCode as Abs;
+        let finger = |value| InputEvent::new(EventType::KEY.0, KeyCode::BTN_TOOL_FINGER.0, value);
+        let frames = vec![
+            vec![
+                abs(Abs::ABS_X, 320),
+                abs(Abs::ABS_Y, 400),
+                abs(Abs::ABS_PRESSURE, 10),
+                finger(1),
+            ],
+            vec![abs(Abs::ABS_PRESSURE, 49)],
+            vec![abs(Abs::ABS_PRESSURE, 50)],
+            vec![finger(0)],
+        ];
+
+        assert_eq!(
+            pressed_per_frame(&frames, &mut gate),
+            vec![vec![], vec![], vec![0], vec![]]
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use super::{ContactChange, NumpadState, PalmRejection};
+use crate::input::TouchpadBounds;
+use evdev::{AbsoluteAxisCode, EventType, InputEvent};
+
+/// Touchpad events captured with `evemu-record`, replayed by tests
+pub struct Recording {
+    /// Position ranges from the recording's `A:` lines
+    pub bounds: TouchpadBounds,
+    /// `E:` lines with their kernel timestamps
+    pub events: Vec<InputEvent>,
+}
+
+impl Recording {
+    /// Parse evemu text such as `E: 0.040000 0003 0035 1500`; other lines are skipped
+    pub fn parse(source: &str) -> Self {
+        let mut bounds = TouchpadBounds {
+            min_x: 0,
+            max_x: 0,
+            min_y: 0,
+            max_y: 0,
+        };
+        let mut events = Vec::new();
+
+        for line in source.lines() {
+            // Drop trailing `# EV_ABS / ...` annotations
+            let line = line.split('#').next().unwrap_or_default();
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                ["A:", code, min, max, ..] => {
+                    let code = u16::from_str_radix(code, 16).expect("axis code");
+                    let (min, max) = (min.parse().expect("min"), max.parse().expect("max"));
+                    if code == AbsoluteAxisCode::ABS_MT_POSITION_X.0 {
+                        (bounds.min_x, bounds.max_x) = (min, max);
+                    } else if code == AbsoluteAxisCode::ABS_MT_POSITION_Y.0 {
+                        (bounds.min_y, bounds.max_y) = (min, max);
+                    }
+                }
+                ["E:", time, type_, code, value] => {
+                    let (sec, usec) = time.split_once('.').expect("timestamp");
+                    events.push(InputEvent::from(libc::input_event {
+                        time: libc::timeval {
+                            tv_sec: sec.parse().expect("seconds"),
+                            tv_usec: usec.parse().expect("microseconds"),
+                        },
+                        type_: u16::from_str_radix(type_, 16).expect("event type"),
+                        code: u16::from_str_radix(code, 16).expect("event code"),
+                        value: value.parse().expect("event value"),
+                    }));
+                }
+                _ => {}
+            }
+        }
+
+        Self { bounds, events }
+    }
+
+    /// Thumb resting on the dot key while a palm lands on enter, with the
+    /// thresholds that reject both
+    pub fn palm_on_enter() -> (Self, PalmRejection) {
+        let recording = Self::parse(include_str!("../../tests/fixtures/palm_on_enter.evemu"));
+        let palm_rejection = PalmRejection {
+            touch_major: Some(1200),
+            touch_minor: None,
+            pressure: Some(120),
+            tool_type: true,
+        };
+        (recording, palm_rejection)
+    }
+
+    /// Feed every event to `state`, returning the changes of each completed frame
+    pub fn replay(&self, state: &mut NumpadState) -> Vec<Vec<ContactChange>> {
+        let mut frames = Vec::new();
+        for event in &self.events {
+            let changes = state.handle_event(event, &self.bounds);
+            if event.event_type() == EventType::SYNCHRONIZATION {
+                frames.push(changes);
+            }
+        }
+        frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn parses_axes_and_timestamped_events() {
+        let recording = Recording::parse(
+            "# EVEMU 1.3\n\
+             A: 35 0 3000 0 0 31\n\
+             A: 36 0 2000 0 0 31\n\
+             E: 1.040000 0003 0039 -001\t# EV_ABS / ABS_MT_TRACKING_ID -1\n",
+        );
+
+        assert_eq!(
+            (recording.bounds.max_x, recording.bounds.max_y),
+            (3000, 2000)
+        );
+        let event = recording.events[0];
+        assert_eq!(event.event_type(), EventType::ABSOLUTE);
+        assert_eq!(event.code(), AbsoluteAxisCode::ABS_MT_TRACKING_ID.0);
+        assert_eq!(event.value(), -1);
+        assert_eq!(event.timestamp(), UNIX_EPOCH + Duration::from_millis(1040));
+    }
+}
@@ -0,0 +1,141 @@
+use evdev::KeyCode;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+/// Delay and rate of the driver's own key repeat
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RepeatSettings {
+    /// Time a finger must stay on a key before it starts repeating
+    #[serde(default = "default_delay_ms")]
+    pub delay_ms: u64,
+    /// Repeats per second after the delay
+    #[serde(default = "default_rate")]
+    pub rate: u32,
+}
+
+fn default_delay_ms() -> u64 {
+    500
+}
+
+fn default_rate() -> u32 {
+    25
+}
+
+impl Default for RepeatSettings {
+    fn default() -> Self {
+        Self {
+            delay_ms: default_delay_ms(),
+            rate: default_rate(),
+        }
+    }
+}
+
+impl RepeatSettings {
+    pub fn check(&self) -> Result<(), String> {
+        if !(1..=100).contains(&self.rate) {
+            return Err(format!("repeat rate must be in 1..=100, got {}", self.rate));
+        }
+        Ok(())
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(1) / self.rate
+    }
+}
+
+#[derive(Debug)]
+struct RepeatingKey {
+    slot: usize,
+    key: KeyCode,
+    next: Instant,
+}
+
+/// Keys clicked again at a fixed rate while the finger that pressed them stays
+/// down
+#[derive(Debug)]
+pub struct KeyRepeat {
+    settings: RepeatSettings,
+    keys: Vec<RepeatingKey>,
+}
+
+impl KeyRepeat {
+    pub fn new(settings: RepeatSettings) -> Self {
+        Self {
+            settings,
+            keys: Vec::new(),
+        }
+    }
+
+    /// Start repeating `key` for the finger in `slot`, pressed at `now`
+    pub fn start(&mut self, slot: usize, key: KeyCode, now: Instant) {
+        self.stop(slot);
+        self.keys.push(RepeatingKey {
+            slot,
+            key,
+            next: now + Duration::from_millis(self.settings.delay_ms),
+        });
+    }
+
+    /// Stop the repeat of the finger in `slot`, e.g. when it lifts
+    pub fn stop(&mut self, slot: usize) {
+        self.keys.retain(|k| k.slot != slot);
+    }
+
+    pub fn clear(&mut self) {
+        self.keys.clear();
+    }
+
+    /// When the next repeat is due, if any key is repeating
+    pub fn next_due(&self) -> Option<Instant> {
+        self.keys.iter().map(|k| k.next).min()
+    }
+
+    /// Keys due for a repeat at `now`; a late check repeats each key once
+    /// rather than catching up
+    pub fn due(&mut self, now: Instant) -> Vec<KeyCode> {
+        let interval = self.settings.interval();
+        self.keys
+            .iter_mut()
+            .filter(|k| k.next <= now)
+            .map(|k| {
+                k.next = now + interval;
+                k.key
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeats_after_delay_at_rate_until_finger_lifts() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut repeat = KeyRepeat::new(RepeatSettings {
+            delay_ms: 500,
+            rate: 20,
+        });
+
+        repeat.start(0, KeyCode::KEY_BACKSPACE, start);
+        repeat.start(1, KeyCode::KEY_KP5, at(300));
+        assert_eq!(repeat.next_due(), Some(at(500)));
+        assert!(repeat.due(at(499)).is_empty());
+        assert_eq!(repeat.due(at(500)), vec![KeyCode::KEY_BACKSPACE]);
+        assert!(repeat.due(at(549)).is_empty());
+        assert_eq!(repeat.due(at(550)), vec![KeyCode::KEY_BACKSPACE]);
+        // A late check repeats once and the next repeat counts from it
+        assert_eq!(
+            repeat.due(at(900)),
+            vec![KeyCode::KEY_BACKSPACE, KeyCode::KEY_KP5]
+        );
+        assert!(repeat.due(at(949)).is_empty());
+
+        repeat.stop(0);
+        assert_eq!(repeat.due(at(950)), vec![KeyCode::KEY_KP5]);
+        repeat.clear();
+        assert_eq!(repeat.next_due(), None);
+    }
+}
@@ -0,0 +1,158 @@
+use super::{Contact, ContactChange};
+use crate::i2c::Brightness;
+use crate::layouts::{NumpadLayout, SliderTarget, SliderZone};
+use evdev::KeyCode;
+
+/// Output of a drag along a slider
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SliderStep {
+    Brightness(Brightness),
+    Key(KeyCode),
+}
+
+/// Finger that landed on a slider
+#[derive(Debug)]
+struct Drag {
+    tracking_id: i32,
+    slider: SliderZone,
+    /// Position along the slider where the next key step is counted from
+    anchor: f64,
+    /// Brightness last sent for this finger
+    brightness: Option<Brightness>,
+}
+
+/// Turns fingers that land on slider zones into brightness changes and key
+/// steps; a finger stays with its slider until it lifts, even if it slides off
+#[derive(Debug, Default)]
+pub struct SliderTracker {
+    drags: Vec<Drag>,
+}
+
+impl SliderTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.drags.clear();
+    }
+
+    /// Feed a completed frame: the changes it produced and the fingers still
+    /// on the pad
+    pub fn process(
+        &mut self,
+        changes: &[ContactChange],
+        contacts: &[Contact],
+        layout: &dyn NumpadLayout,
+    ) -> Vec<SliderStep> {
+        for change in changes {
+            match change {
+                ContactChange::Down(contact) => {
+                    let position = contact.position;
+                    if let Some(slider) = layout.slider_at_position(position.x, position.y) {
+                        self.drags.push(Drag {
+                            tracking_id: contact.tracking_id,
+                            slider: slider.clone(),
+                            anchor: layout.slider_fraction(slider, position.x, position.y),
+                            brightness: None,
+                        });
+                    }
+                }
+                ContactChange::Up(contact) => {
+                    self.drags.retain(|d| d.tracking_id != contact.tracking_id);
+                }
+            }
+        }
+
+        let mut steps = Vec::new();
+        for drag in &mut self.drags {
+            let Some(contact) = contacts.iter().find(|c| c.tracking_id == drag.tracking_id) else {
+                continue;
+            };
+            let position = contact.position;
+            let fraction = layout.slider_fraction(&drag.slider, position.x, position.y);
+            match drag.slider.target {
+                SliderTarget::Brightness => {
+                    let level = Brightness::from_fraction(fraction);
+                    if drag.brightness != Some(level) {
+                        drag.brightness = Some(level);
+                        steps.push(SliderStep::Brightness(level));
+                    }
+                }
+                SliderTarget::Keys {
+                    decrease,
+                    increase,
+                    step,
+                } => {
+                    while fraction - drag.anchor >= step {
+                        drag.anchor += step;
+                        steps.push(SliderStep::Key(increase));
+                    }
+                    while drag.anchor - fraction >= step {
+                        drag.anchor -= step;
+                        steps.push(SliderStep::Key(decrease));
+                    }
+                }
+            }
+        }
+        steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::FileLayout;
+    use crate::numpad::{NumpadState, Recording};
+    use evdev::EventType;
+
+    #[test]
+    fn drags_set_brightness_and_step_volume() {
+        let recording = Recording::parse(include_str!("../../tests/fixtures/edge_sliders.evemu"));
+        let layout = FileLayout::from_toml(
+            r#"
+name = "edge-sliders"
+
+[[sliders]]
+action = "brightness"
+axis = "horizontal"
+x = [0.06, 0.75]
+y = [0.00, 0.05]
+
+[[sliders]]
+action = { decrease = "KEY_VOLUMEDOWN", increase = "KEY_VOLUMEUP", step = 0.05 }
+axis = "vertical"
+x = [0.95, 1.00]
+y = [0.30, 0.95]
+"#,
+        )
+        .unwrap();
+        let mut state = NumpadState::new();
+        let mut sliders = SliderTracker::new();
+
+        let mut steps = Vec::new();
+        for event in &recording.events {
+            let changes = state.handle_event(event, &recording.bounds);
+            if event.event_type() == EventType::SYNCHRONIZATION {
+                steps.extend(sliders.process(&changes, &state.contacts, &layout));
+            }
+        }
+
+        assert_eq!(
+            steps,
+            vec![
+                SliderStep::Brightness(Brightness::Low),
+                SliderStep::Brightness(Brightness::Medium),
+                SliderStep::Brightness(Brightness::High),
+                // 0.1 of the pad height up the 0.65 long right edge slider is
+                // 0.15 of its length, three steps of 0.05; half of that back
+                // down is one
+                SliderStep::Key(KeyCode::KEY_VOLUMEUP),
+                SliderStep::Key(KeyCode::KEY_VOLUMEUP),
+                SliderStep::Key(KeyCode::KEY_VOLUMEUP),
+                SliderStep::Key(KeyCode::KEY_VOLUMEDOWN),
+            ]
+        );
+        assert!(sliders.drags.is_empty());
+    }
+}
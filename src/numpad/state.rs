@@ -1,42 +1,108 @@
+use super::PalmRejection;
 use crate::i2c::Brightness;
-use evdev::KeyCode;
+use crate::input::TouchpadBounds;
+use evdev::{AbsoluteAxisCode, EventType, InputEvent, KeyCode, SynchronizationCode};
+use log::debug;
+use std::time::{Duration, SystemTime};
 
 /// Touch position in normalized coordinates (0.0 - 1.0)
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub struct TouchPosition {
     pub x: f64,
     pub y: f64,
 }
 
-/// Corner detection zones
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Corner {
-    TopRight, // Numpad toggle
-    TopLeft,  // Calculator/brightness
-    None,
+/// Finger on the touchpad, identified by its multitouch slot and tracking ID
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contact {
+    pub slot: usize,
+    pub tracking_id: i32,
+    pub position: TouchPosition,
+    /// Key held down on behalf of this finger, released when it lifts
+    pub pressed_key: Option<KeyCode>,
+    /// Latest `ABS_MT_TOUCH_MAJOR`, `ABS_MT_TOUCH_MINOR`, `ABS_MT_PRESSURE`
+    /// (`ABS_PRESSURE` on single-touch pads) and `ABS_MT_TOOL_TYPE`, when the
+    /// touchpad reports them
+    pub touch_major: Option<i32>,
+    pub touch_minor: Option<i32>,
+    pub pressure: Option<i32>,
+    pub tool_type: Option<i32>,
+    /// Where the finger landed
+    pub start: TouchPosition,
+    /// Furthest the finger has moved from `start`, in normalized units
+    pub travel: f64,
+    /// Kernel timestamps of the landing frame and the latest frame
+    pub landed_at: SystemTime,
+    pub last_seen: SystemTime,
+    /// Whether the landing has been reported by a `SYN_REPORT` yet
+    landed: bool,
+    /// Rejected as a palm; the rest of the touch is ignored
+    rejected: bool,
 }
 
-impl TouchPosition {
-    pub fn corner(&self) -> Corner {
-        // Top-right: x > 80%, y < 25%
-        if self.x > 0.80 && self.y < 0.25 {
-            Corner::TopRight
-        }
-        // Top-left: x < 6%, y < 7%
-        else if self.x < 0.06 && self.y < 0.07 {
-            Corner::TopLeft
-        } else {
-            Corner::None
+impl Contact {
+    fn new(slot: usize, tracking_id: i32) -> Self {
+        Self {
+            slot,
+            tracking_id,
+            position: TouchPosition::default(),
+            pressed_key: None,
+            touch_major: None,
+            touch_minor: None,
+            pressure: None,
+            tool_type: None,
+            start: TouchPosition::default(),
+            travel: 0.0,
+            landed_at: SystemTime::UNIX_EPOCH,
+            last_seen: SystemTime::UNIX_EPOCH,
+            landed: false,
+            rejected: false,
         }
     }
+
+    /// Whether the contact was rejected as a palm
+    pub fn is_rejected(&self) -> bool {
+        self.rejected
+    }
+
+    /// Time from the landing frame to the latest frame, by kernel timestamps
+    pub fn duration(&self) -> Duration {
+        self.last_seen
+            .duration_since(self.landed_at)
+            .unwrap_or_default()
+    }
+}
+
+/// Finger landing or lifting, reported once its frame is complete
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContactChange {
+    Down(Contact),
+    /// The contact as it was when it lifted or was rejected as a palm,
+    /// including any key it still holds
+    Up(Contact),
 }
 
 /// State machine for numpad operation
 pub struct NumpadState {
     pub enabled: bool,
     pub brightness: Brightness,
-    pub current_position: TouchPosition,
-    pub pressed_key: Option<KeyCode>,
+    /// Fingers on the pad in landing order
+    pub contacts: Vec<Contact>,
+    /// Index of the active key layer; 0 is the numpad itself
+    pub active_layer: usize,
+    /// Thresholds for ignoring palms and resting thumbs
+    pub palm_rejection: PalmRejection,
+    /// Slot that `ABS_MT_*` events currently apply to
+    slot: usize,
+    /// Set by the first slot or tracking ID event; single-touch events are
+    /// ignored from then on
+    multitouch: bool,
+    /// Position from single-touch events, used for the one contact of
+    /// touchpads without slots
+    pointer: TouchPosition,
+    pointer_pressure: Option<i32>,
+    /// Contacts lifted in the current frame
+    lifted: Vec<Contact>,
 }
 
 impl NumpadState {
@@ -44,28 +110,200 @@ impl NumpadState {
         Self {
             enabled: false,
             brightness: Brightness::High, // Start at full brightness
-            current_position: TouchPosition::default(),
-            pressed_key: None,
+            contacts: Vec::new(),
+            active_layer: 0,
+            palm_rejection: PalmRejection::default(),
+            slot: 0,
+            multitouch: false,
+            pointer: TouchPosition::default(),
+            pointer_pressure: None,
+            lifted: Vec::new(),
         }
     }
 
-    /// Update X position from raw touchpad value
-    pub fn update_x(&mut self, value: i32, min_x: i32, max_x: i32) {
-        self.current_position.x = normalize_axis(value, min_x, max_x);
+    /// Feed one touchpad event; returns the fingers that landed or lifted
+    /// when it completes a frame
+    pub fn handle_event(
+        &mut self,
+        event: &InputEvent,
+        bounds: &TouchpadBounds,
+    ) -> Vec<ContactChange> {
+        match event.event_type() {
+            EventType::ABSOLUTE => {
+                self.handle_axis(AbsoluteAxisCode(event.code()), event.value(), bounds)
+            }
+            EventType::KEY
+                if !self.multitouch && KeyCode(event.code()) == KeyCode::BTN_TOOL_FINGER =>
+            {
+                if event.value() == 0 {
+                    self.lift(0);
+                } else if self.contact(0).is_none() {
+                    self.contacts.push(Contact::new(0, 0));
+                }
+            }
+            EventType::SYNCHRONIZATION
+                if SynchronizationCode(event.code()) == SynchronizationCode::SYN_REPORT =>
+            {
+                return self.finish_frame(event.timestamp());
+            }
+            _ => {}
+        }
+        Vec::new()
+    }
+
+    fn handle_axis(&mut self, code: AbsoluteAxisCode, value: i32, bounds: &TouchpadBounds) {
+        match code {
+            AbsoluteAxisCode::ABS_MT_SLOT => {
+                self.multitouch = true;
+                self.slot = usize::try_from(value).unwrap_or(0);
+            }
+            AbsoluteAxisCode::ABS_MT_TRACKING_ID => {
+                self.multitouch = true;
+                let slot = self.slot;
+                // A new ID without a -1 first means the old finger lifted unseen
+                if self.contact(slot).is_some_and(|c| c.tracking_id != value) {
+                    self.lift(slot);
+                }
+                if value >= 0 && self.contact(slot).is_none() {
+                    self.contacts.push(Contact::new(slot, value));
+                }
+            }
+            AbsoluteAxisCode::ABS_MT_POSITION_X if self.multitouch => {
+                if let Some(contact) = self.contact_mut(self.slot) {
+                    contact.position.x = normalize_axis(value, bounds.min_x, bounds.max_x);
+                }
+            }
+            AbsoluteAxisCode::ABS_MT_POSITION_Y if self.multitouch => {
+                if let Some(contact) = self.contact_mut(self.slot) {
+                    contact.position.y = normalize_axis(value, bounds.min_y, bounds.max_y);
+                }
+            }
+            AbsoluteAxisCode::ABS_MT_TOUCH_MAJOR => {
+                if let Some(contact) = self.contact_mut(self.slot) {
+                    contact.touch_major = Some(value);
+                }
+            }
+            AbsoluteAxisCode::ABS_MT_TOUCH_MINOR => {
+                if let Some(contact) = self.contact_mut(self.slot) {
+                    contact.touch_minor = Some(value);
+                }
+            }
+            AbsoluteAxisCode::ABS_MT_PRESSURE => {
+                if let Some(contact) = self.contact_mut(self.slot) {
+                    contact.pressure = Some(value);
+                }
+            }
+            AbsoluteAxisCode::ABS_MT_TOOL_TYPE => {
+                if let Some(contact) = self.contact_mut(self.slot) {
+                    contact.tool_type = Some(value);
+                }
+            }
+            AbsoluteAxisCode::ABS_MT_POSITION_X | AbsoluteAxisCode::ABS_X if !self.multitouch => {
+                self.pointer.x = normalize_axis(value, bounds.min_x, bounds.max_x);
+            }
+            AbsoluteAxisCode::ABS_MT_POSITION_Y | AbsoluteAxisCode::ABS_Y if !self.multitouch => {
+                self.pointer.y = normalize_axis(value, bounds.min_y, bounds.max_y);
+            }
+            AbsoluteAxisCode::ABS_PRESSURE if !self.multitouch => {
+                self.pointer_pressure = Some(value);
+            }
+            _ => {}
+        }
+    }
+
+    fn lift(&mut self, slot: usize) {
+        if let Some(index) = self.contacts.iter().position(|c| c.slot == slot) {
+            let contact = self.contacts.remove(index);
+            // A finger that lands and lifts within one frame was never reported,
+            // and a rejected palm was already reported as lifted
+            if contact.landed && !contact.rejected {
+                self.lifted.push(contact);
+            }
+        }
+    }
+
+    fn finish_frame(&mut self, time: SystemTime) -> Vec<ContactChange> {
+        if !self.multitouch {
+            let (pointer, pressure) = (self.pointer, self.pointer_pressure);
+            if let Some(contact) = self.contact_mut(0) {
+                contact.position = pointer;
+                contact.pressure = pressure;
+            }
+        }
+
+        let mut changes: Vec<ContactChange> = self
+            .lifted
+            .drain(..)
+            .map(|mut contact| {
+                contact.last_seen = time;
+                ContactChange::Up(contact)
+            })
+            .collect();
+        for contact in self.contacts.iter_mut().filter(|c| !c.rejected) {
+            if contact.landed {
+                let moved = (contact.position.x - contact.start.x)
+                    .hypot(contact.position.y - contact.start.y);
+                contact.travel = contact.travel.max(moved);
+            } else {
+                contact.start = contact.position;
+                contact.landed_at = time;
+            }
+            contact.last_seen = time;
+
+            if let Some(reason) = self.palm_rejection.reason(contact) {
+                debug!(
+                    "Rejected finger {} in slot {} as a palm: {}",
+                    contact.tracking_id, contact.slot, reason
+                );
+                contact.rejected = true;
+                // A palm that grew out of a reported touch lifts it early
+                if contact.landed {
+                    changes.push(ContactChange::Up(contact.clone()));
+                    contact.pressed_key = None;
+                }
+                contact.landed = true;
+            } else if !contact.landed {
+                contact.landed = true;
+                changes.push(ContactChange::Down(contact.clone()));
+            }
+        }
+        changes
+    }
+
+    /// Finger currently in `slot`
+    pub fn contact(&self, slot: usize) -> Option<&Contact> {
+        self.contacts.iter().find(|c| c.slot == slot)
+    }
+
+    pub fn contact_mut(&mut self, slot: usize) -> Option<&mut Contact> {
+        self.contacts.iter_mut().find(|c| c.slot == slot)
+    }
+
+    /// Returns true when any finger holds `key`
+    pub fn is_key_held(&self, key: KeyCode) -> bool {
+        self.contacts.iter().any(|c| c.pressed_key == Some(key))
     }
 
-    /// Update Y position from raw touchpad value
-    pub fn update_y(&mut self, value: i32, min_y: i32, max_y: i32) {
-        self.current_position.y = normalize_axis(value, min_y, max_y);
+    /// Detach every held key from its finger, e.g. when the numpad turns off
+    pub fn take_pressed_keys(&mut self) -> Vec<KeyCode> {
+        self.contacts
+            .iter_mut()
+            .filter_map(|c| c.pressed_key.take())
+            .collect()
     }
 
     /// Cycle to next brightness level
     pub fn cycle_brightness(&mut self) {
         self.brightness = self.brightness.next();
     }
+
+    /// Cycle to the next of `layer_count` key layers, wrapping to the numpad
+    pub fn cycle_layer(&mut self, layer_count: usize) {
+        self.active_layer = (self.active_layer + 1) % layer_count.max(1);
+    }
 }
 
-fn normalize_axis(value: i32, min: i32, max: i32) -> f64 {
+pub(super) fn normalize_axis(value: i32, min: i32, max: i32) -> f64 {
     if max <= min {
         return 0.0;
     }
@@ -82,6 +320,20 @@ impl Default for NumpadState {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::layouts::{G634jyLayout, NumpadLayout};
+    use crate::numpad::Recording;
+
+    /// Change written as e.g. `down 0/101 at 0.14,0.15` (slot/tracking ID)
+    fn describe(change: &ContactChange) -> String {
+        let (kind, c) = match change {
+            ContactChange::Down(c) => ("down", c),
+            ContactChange::Up(c) => ("up", c),
+        };
+        format!(
+            "{} {}/{} at {:.2},{:.2}",
+            kind, c.slot, c.tracking_id, c.position.x, c.position.y
+        )
+    }
 
     #[test]
     fn normalizes_and_clamps_axis_values() {
@@ -92,12 +344,143 @@ mod tests {
     }
 
     #[test]
-    fn corner_detection_uses_expected_zones() {
+    fn tracks_each_finger_by_slot_in_two_finger_roll() {
+        let recording =
+            Recording::parse(include_str!("../../tests/fixtures/two_finger_roll.evemu"));
+        let mut state = NumpadState::new();
+        let frames = recording.replay(&mut state);
+
+        let summary: Vec<Vec<String>> = frames
+            .iter()
+            .map(|changes| changes.iter().map(describe).collect())
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                vec!["down 0/101 at 0.14,0.15"],
+                // BTN_TOOL_FINGER dropping to 0 for the second finger lifts nothing
+                vec!["down 1/102 at 0.50,0.15"],
+                vec![],
+                // The emulated ABS_X/ABS_Y pointer following the second finger is ignored
+                vec!["up 0/101 at 0.14,0.15"],
+                vec!["up 1/102 at 0.50,0.65"],
+            ]
+        );
+        assert!(state.contacts.is_empty());
+    }
+
+    #[test]
+    fn second_finger_never_retargets_or_releases_first_key() {
+        let recording =
+            Recording::parse(include_str!("../../tests/fixtures/tap_while_holding.evemu"));
+        let layout = G634jyLayout::new();
+        let mut state = NumpadState::new();
+        let mut released = Vec::new();
+
+        for event in &recording.events {
+            for change in state.handle_event(event, &recording.bounds) {
+                match change {
+                    ContactChange::Down(contact) => {
+                        let key = layout.key_at_position(contact.position.x, contact.position.y);
+                        state.contact_mut(contact.slot).unwrap().pressed_key = key;
+                    }
+                    ContactChange::Up(contact) => {
+                        released.push((contact.tracking_id, contact.pressed_key));
+                        if contact.tracking_id == 202 {
+                            // The held finger still owns 7 after the tap lifted
+                            assert_eq!(state.contacts.len(), 1);
+                            assert_eq!(state.contacts[0].pressed_key, Some(KeyCode::KEY_KP7));
+                        }
+                    }
+                }
+            }
+        }
+
         assert_eq!(
-            TouchPosition { x: 0.90, y: 0.10 }.corner(),
-            Corner::TopRight
+            released,
+            vec![(202, Some(KeyCode::KEY_KP9)), (201, Some(KeyCode::KEY_KP7))]
         );
-        assert_eq!(TouchPosition { x: 0.05, y: 0.05 }.corner(), Corner::TopLeft);
-        assert_eq!(TouchPosition { x: 0.50, y: 0.50 }.corner(), Corner::None);
+    }
+
+    #[test]
+    fn rejects_palms_by_size_pressure_and_tool_type() {
+        let (recording, palm_rejection) = Recording::palm_on_enter();
+        let mut state = NumpadState::new();
+        state.palm_rejection = palm_rejection;
+        let frames = recording.replay(&mut state);
+
+        let changes: Vec<String> = frames.iter().flatten().map(describe).collect();
+        assert_eq!(
+            changes,
+            vec![
+                "down 0/301 at 0.32,0.40",
+                // The resting palm lands with a large contact and is never reported;
+                // the thumb is a finger at first and lifts once its pressure grows
+                "down 2/303 at 0.50,0.87",
+                "up 2/303 at 0.50,0.87",
+                "up 0/301 at 0.32,0.40",
+            ]
+        );
+        assert!(state.contacts.is_empty());
+
+        // Without thresholds only the touchpad's own palm flag counts
+        let mut state = NumpadState::new();
+        let changes: Vec<String> = recording
+            .replay(&mut state)
+            .iter()
+            .flatten()
+            .map(describe)
+            .collect();
+        assert_eq!(changes.len(), 6, "{changes:?}");
+        assert!(!changes.iter().any(|c| c.contains("/304")), "{changes:?}");
+    }
+
+    #[test]
+    fn single_touch_pads_use_tool_finger_and_pointer() {
+        let bounds = TouchpadBounds {
+            min_x: 0,
+            max_x: 1000,
+            min_y: 0,
+            max_y: 1000,
+        };
+        let mut state = NumpadState::new();
+        let events = [
+            InputEvent::new(EventType::ABSOLUTE.0, AbsoluteAxisCode::ABS_X.0, 250),
+            InputEvent::new(EventType::ABSOLUTE.0, AbsoluteAxisCode::ABS_Y.0, 750),
+            InputEvent::new(EventType::KEY.0, KeyCode::BTN_TOOL_FINGER.0, 1),
+        ];
+        for event in &events {
+            assert!(state.handle_event(event, &bounds).is_empty());
+        }
+        let syn = InputEvent::new(
+            EventType::SYNCHRONIZATION.0,
+            SynchronizationCode::SYN_REPORT.0,
+            0,
+        );
+
+        let changes = state.handle_event(&syn, &bounds);
+        assert!(
+            matches!(&changes[..], [ContactChange::Down(c)] if c.position == TouchPosition { x: 0.25, y: 0.75 })
+        );
+        let up = InputEvent::new(EventType::KEY.0, KeyCode::BTN_TOOL_FINGER.0, 0);
+        state.handle_event(&up, &bounds);
+        assert!(matches!(
+            &state.handle_event(&syn, &bounds)[..],
+            [ContactChange::Up(_)]
+        ));
+    }
+
+    #[test]
+    fn cycles_layers_and_wraps_to_numpad() {
+        let mut state = NumpadState::new();
+
+        state.cycle_layer(3);
+        assert_eq!(state.active_layer, 1);
+        state.cycle_layer(3);
+        assert_eq!(state.active_layer, 2);
+        state.cycle_layer(3);
+        assert_eq!(state.active_layer, 0);
+        state.cycle_layer(1);
+        assert_eq!(state.active_layer, 0);
     }
 }
@@ -0,0 +1,135 @@
+use super::Contact;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Limits within which a touch counts as a tap rather than a drag
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TapSettings {
+    /// Longest time from landing to lifting, in milliseconds
+    #[serde(default = "default_max_duration_ms")]
+    pub max_duration_ms: u64,
+    /// Furthest the finger may move from where it landed, in normalized units
+    #[serde(default = "default_max_distance")]
+    pub max_distance: f64,
+}
+
+fn default_max_duration_ms() -> u64 {
+    250
+}
+
+fn default_max_distance() -> f64 {
+    0.03
+}
+
+impl Default for TapSettings {
+    fn default() -> Self {
+        Self {
+            max_duration_ms: default_max_duration_ms(),
+            max_distance: default_max_distance(),
+        }
+    }
+}
+
+impl TapSettings {
+    pub fn check(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.max_distance) {
+            return Err(format!(
+                "tap max_distance must be in 0.0..=1.0, got {}",
+                self.max_distance
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns true when a lifted contact was short and still enough to be a tap
+    pub fn is_tap(&self, contact: &Contact) -> bool {
+        contact.duration() <= Duration::from_millis(self.max_duration_ms)
+            && contact.travel <= self.max_distance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::{G634jyLayout, NumpadLayout};
+    use crate::numpad::{ContactChange, NumpadState, PressMode, Recording};
+    use evdev::KeyCode;
+
+    #[test]
+    fn only_short_still_touches_are_taps() {
+        let recording = Recording::parse(include_str!("../../tests/fixtures/tap_and_drag.evemu"));
+        let layout = G634jyLayout::new();
+        let tap = TapSettings::default();
+        let lifted: Vec<Contact> = recording
+            .replay(&mut NumpadState::new())
+            .into_iter()
+            .flatten()
+            .filter_map(|change| match change {
+                ContactChange::Up(contact) => Some(contact),
+                ContactChange::Down(_) => None,
+            })
+            .collect();
+
+        let decisions: Vec<(Option<KeyCode>, u128, bool)> = lifted
+            .iter()
+            .map(|contact| {
+                (
+                    layout.key_at_position(contact.start.x, contact.start.y),
+                    contact.duration().as_millis(),
+                    tap.is_tap(contact),
+                )
+            })
+            .collect();
+        assert_eq!(
+            decisions,
+            vec![
+                // Quick tap with a little jitter
+                (Some(KeyCode::KEY_KP7), 84, true),
+                // Fast cursor drag from 5 across to 9
+                (Some(KeyCode::KEY_KP5), 180, false),
+                // Finger resting on 1
+                (Some(KeyCode::KEY_KP1), 600, false),
+            ]
+        );
+
+        // A longer limit accepts the resting finger but still not the drag
+        let patient = TapSettings {
+            max_duration_ms: 800,
+            ..tap
+        };
+        let taps: Vec<i32> = lifted
+            .iter()
+            .filter(|contact| patient.is_tap(contact))
+            .map(|contact| contact.tracking_id)
+            .collect();
+        assert_eq!(taps, vec![401, 403]);
+    }
+
+    #[test]
+    fn palms_never_tap_a_key() {
+        let (recording, palm_rejection) = Recording::palm_on_enter();
+        let layout = G634jyLayout::new();
+        let tap = TapSettings::default();
+        let mut state = NumpadState::new();
+        state.palm_rejection = palm_rejection;
+        let thumb = recording
+            .replay(&mut state)
+            .into_iter()
+            .flatten()
+            .find_map(|change| match change {
+                ContactChange::Up(contact) if contact.tracking_id == 303 => Some(contact),
+                _ => None,
+            })
+            .unwrap();
+
+        // The thumb on the dot key is short and still enough for a tap, but
+        // it lifted because it was rejected as a palm
+        assert!(thumb.is_rejected() && tap.is_tap(&thumb));
+        assert_eq!(
+            layout.key_at_position(thumb.start.x, thumb.start.y),
+            Some(KeyCode::KEY_KPDOT)
+        );
+        assert_eq!(PressMode::Touch.lift(&thumb, Some(tap), &layout, 0), None);
+    }
+}